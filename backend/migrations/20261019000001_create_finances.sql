@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS finances (
+    account TEXT NOT NULL,
+    date DATETIME NOT NULL,
+    description TEXT NOT NULL,
+    amount REAL NOT NULL,
+    l1_tag TEXT NOT NULL,
+    l2_tag TEXT NOT NULL,
+    l3_tag TEXT NOT NULL
+);
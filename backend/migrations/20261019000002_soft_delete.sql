@@ -0,0 +1 @@
+ALTER TABLE finances ADD COLUMN deleted_at DATETIME;
@@ -0,0 +1 @@
+ALTER TABLE finances ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
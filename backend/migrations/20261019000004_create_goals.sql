@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS goals (
+  id INTEGER PRIMARY KEY,
+  name TEXT NOT NULL,
+  target REAL NOT NULL,
+  target_date DATE NOT NULL,
+  account TEXT,
+  tag TEXT,
+  CHECK ((account IS NULL) != (tag IS NULL))
+);
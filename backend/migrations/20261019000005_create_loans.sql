@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS loans (
+  id INTEGER PRIMARY KEY,
+  name TEXT NOT NULL,
+  principal REAL NOT NULL,
+  rate REAL NOT NULL,
+  term_months INTEGER NOT NULL,
+  start_date DATE NOT NULL,
+  payment_day INTEGER NOT NULL,
+  account TEXT NOT NULL
+);
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS holdings (
+  id INTEGER PRIMARY KEY,
+  account TEXT NOT NULL,
+  ticker TEXT NOT NULL,
+  quantity REAL NOT NULL DEFAULT 0,
+  cost_basis REAL NOT NULL DEFAULT 0,
+  UNIQUE (account, ticker)
+);
+
+CREATE TABLE IF NOT EXISTS trades (
+  id INTEGER PRIMARY KEY,
+  finance_id INTEGER NOT NULL,
+  holding_id INTEGER NOT NULL REFERENCES holdings (id),
+  kind TEXT NOT NULL CHECK (kind IN ('Buy', 'Sell', 'Dividend')),
+  quantity REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS prices (
+  ticker TEXT NOT NULL,
+  date DATE NOT NULL,
+  price REAL NOT NULL,
+  PRIMARY KEY (ticker, date)
+);
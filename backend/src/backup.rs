@@ -0,0 +1,386 @@
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{extract::State, http::StatusCode};
+use chrono::{NaiveDateTime, Utc};
+use common::{ApiError, Backup, Config, RestoreRequest};
+use sqlx::{
+    sqlite::{SqlitePoolOptions, SqliteRow},
+    Connection, Row, SqliteConnection,
+};
+
+use crate::{extract::Json, AppState};
+
+const PREFIX: &str = "backup-";
+const EXTENSION: &str = ".tar";
+const TIMESTAMP: &str = "%Y%m%dT%H%M%S%.3f";
+const DATABASE_ENTRY: &str = "finances.sqlite";
+const CONFIG_ENTRY: &str = "config.json";
+
+#[derive(Debug, Clone)]
+pub struct BackupSettings {
+    pub dir: PathBuf,
+    /* How many of the newest archives to keep */
+    pub keep: usize,
+}
+
+pub async fn create_backup(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Json<Backup>), ApiError> {
+    let BackupSettings { dir, keep } = app_state.backups.clone();
+    fs::create_dir_all(&dir).map_err(ApiError::internal)?;
+
+    let created = Utc::now().naive_utc();
+    let name = format!("{PREFIX}{}{EXTENSION}", created.format(TIMESTAMP));
+    let snapshot = dir.join(format!("{name}.sqlite"));
+
+    /* VACUUM INTO reads inside one transaction, so the copy is consistent
+     * even while other connections write */
+    let config = {
+        let config = app_state.config_db.lock().await;
+        sqlx::query("VACUUM INTO ?1")
+            .bind(snapshot.to_string_lossy())
+            .execute(&app_state.pool)
+            .await
+            .map_err(ApiError::internal)?;
+        serde_json::to_vec_pretty(&*config).map_err(ApiError::internal)?
+    };
+
+    let archive = dir.join(&name);
+    let backups = tokio::task::spawn_blocking(move || {
+        let written = write_archive(&archive, &snapshot, &config);
+        let _ = fs::remove_file(&snapshot);
+        written?;
+        prune(&dir, keep)?;
+        list(&dir)
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::internal)?;
+
+    let backup = backups
+        .into_iter()
+        .find(|b| b.name == name)
+        .ok_or_else(|| ApiError::internal("Backup was pruned, is keep set to 0?"))?;
+    Ok((StatusCode::CREATED, Json(backup)))
+}
+
+pub async fn list_backups(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Backup>>, ApiError> {
+    let backups = list(&app_state.backups.dir).map_err(ApiError::internal)?;
+    Ok(Json(backups))
+}
+
+/* The archive is unpacked beside the live database, brought up to the current
+ * migration, then copied over the live tables in a single transaction so
+ * requests in flight see either the old data or the restored data */
+pub async fn restore_backup(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<RestoreRequest>,
+) -> Result<Json<Backup>, ApiError> {
+    let dir = app_state.backups.dir.clone();
+    let backups = list(&dir).map_err(ApiError::internal)?;
+    let Some(backup) = backups.into_iter().find(|b| b.name == request.name) else {
+        return Err(ApiError::not_found(format!(
+            "No backup named {}.",
+            request.name
+        )));
+    };
+
+    let archive = dir.join(&backup.name);
+    let snapshot = dir.join(format!("{}.restore.sqlite", backup.name));
+    let unpacked = snapshot.clone();
+    let config = tokio::task::spawn_blocking(move || read_archive(&archive, &unpacked))
+        .await
+        .map_err(ApiError::internal)?
+        .map_err(|e| ApiError::bad_request(format!("Bad backup archive: {e}.")))?;
+
+    let restored = restore(&app_state, &snapshot, config).await;
+    let _ = fs::remove_file(&snapshot);
+    restored?;
+
+    tracing::info!("restored backup {}", backup.name);
+    Ok(Json(backup))
+}
+
+async fn restore(app_state: &AppState, snapshot: &Path, config: Config) -> Result<(), ApiError> {
+    let path = snapshot.to_string_lossy().into_owned();
+    let backup_pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&format!("sqlite://{path}"))
+        .await
+        .map_err(ApiError::internal)?;
+    let migrated = sqlx::migrate!().run(&backup_pool).await;
+    backup_pool.close().await;
+    migrated.map_err(|e| ApiError::bad_request(format!("Backup can't be migrated: {e}.")))?;
+
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    sqlx::query("ATTACH DATABASE ?1 AS backup")
+        .bind(&path)
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::internal)?;
+    let copied = copy_tables(&mut conn).await;
+    /* Detach even on failure, the connection goes back to the pool */
+    sqlx::query("DETACH DATABASE backup")
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::internal)?;
+    copied.map_err(ApiError::internal)?;
+
+    let json = serde_json::to_string_pretty(&config).map_err(ApiError::internal)?;
+    fs::write(&app_state.config_path, json).map_err(ApiError::internal)?;
+    *app_state.config_db.lock().await = config;
+    Ok(())
+}
+
+/* Rowids are copied too, transactions are addressed by rowid */
+async fn copy_tables(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    /* Tables are emptied in no particular order, so check references at commit */
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut tx)
+        .await?;
+    let tables: Vec<String> = sqlx::query(
+        r#"SELECT name FROM main.sqlite_master
+        WHERE type = "table" AND name NOT LIKE "sqlite_%" AND name != "_sqlx_migrations""#,
+    )
+    .try_map(|row: SqliteRow| row.try_get("name"))
+    .fetch_all(&mut tx)
+    .await?;
+
+    for table in tables {
+        let columns: Vec<String> = sqlx::query("SELECT name FROM pragma_table_info(?1)")
+            .bind(&table)
+            .try_map(|row: SqliteRow| row.try_get("name"))
+            .fetch_all(&mut tx)
+            .await?;
+        let columns = columns
+            .iter()
+            .map(|c| format!(r#""{c}""#))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(r#"DELETE FROM main."{table}""#))
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(&format!(
+            r#"INSERT INTO main."{table}" (rowid, {columns})
+            SELECT rowid, {columns} FROM backup."{table}""#
+        ))
+        .execute(&mut tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+fn write_archive(archive: &Path, snapshot: &Path, config: &[u8]) -> std::io::Result<()> {
+    /* Written under a temporary name so a half written archive is never listed */
+    let partial = archive.with_extension("partial");
+    let mut builder = tar::Builder::new(File::create(&partial)?);
+    builder.append_path_with_name(snapshot, DATABASE_ENTRY)?;
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(config.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().unsigned_abs());
+    header.set_cksum();
+    builder.append_data(&mut header, CONFIG_ENTRY, config)?;
+    builder.into_inner()?.sync_all()?;
+
+    fs::rename(partial, archive)
+}
+
+fn read_archive(archive: &Path, snapshot: &Path) -> Result<Config, String> {
+    let mut tar = tar::Archive::new(File::open(archive).map_err(|e| e.to_string())?);
+    let mut config = None;
+    let mut database = false;
+    for entry in tar.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?.into_owned();
+        if path == Path::new(DATABASE_ENTRY) {
+            entry.unpack(snapshot).map_err(|e| e.to_string())?;
+            database = true;
+        } else if path == Path::new(CONFIG_ENTRY) {
+            let mut json = String::new();
+            entry.read_to_string(&mut json).map_err(|e| e.to_string())?;
+            config = Some(serde_json::from_str(&json).map_err(|e| e.to_string())?);
+        }
+    }
+
+    match (database, config) {
+        (true, Some(config)) => Ok(config),
+        (false, _) => Err(format!("no {DATABASE_ENTRY}")),
+        (true, None) => Err(format!("no {CONFIG_ENTRY}")),
+    }
+}
+
+/* Newest first */
+fn list(dir: &Path) -> std::io::Result<Vec<Backup>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut backups = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(created) = name
+            .strip_prefix(PREFIX)
+            .and_then(|n| n.strip_suffix(EXTENSION))
+            .and_then(|n| NaiveDateTime::parse_from_str(n, TIMESTAMP).ok())
+        else {
+            continue;
+        };
+        backups.push(Backup {
+            name,
+            created,
+            size: entry.metadata()?.len(),
+        });
+    }
+    backups.sort_by_key(|b| Reverse(b.created));
+    Ok(backups)
+}
+
+fn prune(dir: &Path, keep: usize) -> std::io::Result<()> {
+    for backup in list(dir)?.into_iter().skip(keep) {
+        tracing::info!("removing old backup {}", backup.name);
+        fs::remove_file(dir.join(backup.name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqliteConnectOptions;
+
+    use super::*;
+
+    const CONFIG: &str = r#"{"budget": 100.0, "account_list": ["Current"], "period_items": [],
+        "budget_items": [], "tags": {}}"#;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("finances-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn app_state(dir: &Path) -> Arc<AppState> {
+        let options = SqliteConnectOptions::new()
+            .filename(dir.join("finances.sqlite"))
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        let config_path = dir.join("config.json");
+        fs::write(&config_path, CONFIG).unwrap();
+        Arc::new(AppState {
+            config_db: crate::load_config(&config_path).unwrap(),
+            pool,
+            config_path,
+            backups: BackupSettings {
+                dir: dir.join("backups"),
+                keep: 2,
+            },
+        })
+    }
+
+    async fn rows(app_state: &AppState) -> Vec<(i64, String, f64, i64)> {
+        sqlx::query("SELECT rowid, description, amount, version FROM finances ORDER BY rowid")
+            .try_map(|row: SqliteRow| {
+                Ok((
+                    row.try_get(0)?,
+                    row.try_get(1)?,
+                    row.try_get(2)?,
+                    row.try_get(3)?,
+                ))
+            })
+            .fetch_all(&app_state.pool)
+            .await
+            .unwrap()
+    }
+
+    async fn execute(app_state: &AppState, sql: &str) {
+        sqlx::query(sql).execute(&app_state.pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn restore_undoes_changes_since_backup() {
+        let dir = scratch("restore");
+        let app_state = app_state(&dir).await;
+        execute(
+            &app_state,
+            r#"INSERT INTO finances (account, date, description, amount, l1_tag, l2_tag, l3_tag)
+            VALUES ("Current", "2023-05-01", "Lunch", -9.5, "", "", ""),
+            ("Current", "2023-05-02", "Pay", 2000.0, "", "", "")"#,
+        )
+        .await;
+        execute(
+            &app_state,
+            "UPDATE finances SET version = 3 WHERE rowid = 2",
+        )
+        .await;
+        let before = rows(&app_state).await;
+
+        let (_, Json(backup)) = create_backup(State(app_state.clone())).await.unwrap();
+
+        execute(
+            &app_state,
+            "UPDATE finances SET amount = 1.0, version = version + 1 WHERE rowid = 1",
+        )
+        .await;
+        execute(&app_state, "DELETE FROM finances WHERE rowid = 2").await;
+        execute(
+            &app_state,
+            r#"INSERT INTO finances (account, date, description, amount, l1_tag, l2_tag, l3_tag)
+            VALUES ("Current", "2023-05-03", "Later", -1.0, "", "", "")"#,
+        )
+        .await;
+        *app_state.config_db.lock().await =
+            serde_json::from_str(&CONFIG.replace("100.0", "5.0")).unwrap();
+        assert_ne!(rows(&app_state).await, before);
+
+        let request = RestoreRequest {
+            name: backup.name.clone(),
+        };
+        let Json(restored) = restore_backup(State(app_state.clone()), Json(request))
+            .await
+            .unwrap();
+        assert_eq!(restored, backup);
+        assert_eq!(rows(&app_state).await, before);
+
+        let config: Config = serde_json::from_str(CONFIG).unwrap();
+        assert_eq!(*app_state.config_db.lock().await, config);
+
+        app_state.pool.close().await;
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn prune_keeps_the_newest() {
+        let dir = scratch("prune");
+        let names: Vec<String> = (1..=4)
+            .map(|day| format!("{PREFIX}2023050{day}T120000.000{EXTENSION}"))
+            .collect();
+        for name in &names {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        prune(&dir, 2).unwrap();
+        let kept: Vec<String> = list(&dir).unwrap().into_iter().map(|b| b.name).collect();
+        assert_eq!(kept, [names[3].clone(), names[2].clone()]);
+        assert!(dir.join("notes.txt").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+#![warn(clippy::all, clippy::nursery)]
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail};
+use axum::{
+    body::HttpBody,
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use backend::{
+    export,
+    extract::{Json, Query},
+    handlers, ledger, qif,
+    settings::{DatabaseArgs, Settings},
+    AppState,
+};
+use chrono::{NaiveDate, NaiveTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use common::{
+    BudgetProgressOptions, ExportFormat, ExportOptions, QifOptions, Transaction, TransactionFilter,
+};
+
+/* Finds the database and config the same way as the server */
+#[derive(Parser)]
+#[command(name = "finances", about = "Script and cron the finances database")]
+struct Cli {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Add a transaction")]
+    Add {
+        #[arg(long)]
+        account: String,
+        #[arg(long, help = "Defaults to today")]
+        date: Option<NaiveDate>,
+        #[arg(long)]
+        description: String,
+        #[arg(long, allow_hyphen_values = true, help = "Negative for spending")]
+        amount: f64,
+        #[arg(long, num_args = 1..=3, help = "Up to three tags, most general first")]
+        tags: Vec<String>,
+    },
+    #[command(about = "List or search transactions, oldest first")]
+    List {
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[arg(long, help = "Only show the newest N")]
+        limit: Option<usize>,
+    },
+    #[command(about = "Import transactions from a QIF, journal or CSV file")]
+    Import {
+        file: PathBuf,
+        #[arg(long, value_enum, help = "Defaults to the file extension")]
+        format: Option<ImportFormat>,
+        #[arg(long, help = "Account for QIF files without an !Account block")]
+        account: Option<String>,
+    },
+    #[command(about = "Show account totals")]
+    Accounts,
+    #[command(about = "Show budget progress for a month")]
+    Budget {
+        #[arg(long, value_parser = parse_month, help = "YYYY-MM, defaults to this month")]
+        month: Option<NaiveDate>,
+    },
+    #[command(about = "Export transactions")]
+    Export {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+        format: OutputFormat,
+        #[arg(long, short, help = "Defaults to stdout")]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+#[derive(Args)]
+struct FilterArgs {
+    #[arg(long)]
+    account: Option<String>,
+    #[arg(long, help = "Text to find in descriptions")]
+    search: Option<String>,
+    #[arg(long)]
+    l1_tag: Option<String>,
+    #[arg(long)]
+    l2_tag: Option<String>,
+    #[arg(long)]
+    l3_tag: Option<String>,
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    #[arg(long)]
+    to: Option<NaiveDate>,
+}
+
+impl From<FilterArgs> for TransactionFilter {
+    fn from(args: FilterArgs) -> Self {
+        Self {
+            account: args.account,
+            description: args.search,
+            l1_tag: args.l1_tag,
+            l2_tag: args.l2_tag,
+            l3_tag: args.l3_tag,
+            from: args.from,
+            to: args.to,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportFormat {
+    Qif,
+    Journal,
+    Csv,
+}
+
+impl ImportFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "qif" => Some(Self::Qif),
+            "journal" | "ledger" | "hledger" => Some(Self::Journal),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+    Excel,
+    Qif,
+    Journal,
+}
+
+fn parse_month(month: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+        .map_err(|_| format!("expected YYYY-MM, got {month:?}"))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let settings = Settings::load(cli.database.into())?;
+    let state = Arc::new(AppState::new(&settings).await?);
+    let today = Utc::now().date_naive();
+
+    match cli.command {
+        Command::Add {
+            account,
+            date,
+            description,
+            amount,
+            tags,
+        } => {
+            let mut tags = tags.into_iter();
+            let transaction = Transaction {
+                account,
+                date: date.unwrap_or(today).and_time(NaiveTime::default()),
+                description,
+                amount,
+                l1_tag: tags.next().unwrap_or_default(),
+                l2_tag: tags.next().unwrap_or_default(),
+                l3_tag: tags.next().unwrap_or_default(),
+                ..Transaction::default()
+            };
+            let (_, Json(id)) =
+                handlers::create_transaction(State(state), Json(transaction)).await?;
+            println!("Added transaction {id}");
+        }
+        Command::List { filter, limit } => {
+            let transactions = export::fetch_transactions(&state.pool, &filter.into()).await?;
+            let skip = limit.map_or(0, |limit| transactions.len().saturating_sub(limit));
+            for t in &transactions[skip..] {
+                let tags = [&t.l1_tag, &t.l2_tag, &t.l3_tag]
+                    .into_iter()
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(":");
+                println!(
+                    "{:>6}  {}  {:<16} {:>10.2}  {:<30} {}",
+                    t.id,
+                    t.date.date(),
+                    t.account,
+                    t.amount,
+                    tags,
+                    t.description
+                );
+            }
+        }
+        Command::Import {
+            file,
+            format,
+            account,
+        } => {
+            let Some(format) = format.or_else(|| ImportFormat::from_path(&file)) else {
+                bail!("Can't tell the format of {}, pass --format", file.display());
+            };
+            let body = fs::read_to_string(&file)?;
+            let count = match format {
+                ImportFormat::Qif => {
+                    let (_, Json(count)) =
+                        qif::import_qif(Query(QifOptions { account }), State(state), body).await?;
+                    count
+                }
+                ImportFormat::Journal => {
+                    let (_, Json(count)) = ledger::import_journal(State(state), body).await?;
+                    count
+                }
+                ImportFormat::Csv => {
+                    /* The columns written by the CSV and Excel exports */
+                    let mut reader =
+                        csv::Reader::from_reader(body.trim_start_matches('\u{feff}').as_bytes());
+                    let transactions = reader
+                        .deserialize::<Transaction>()
+                        .collect::<Result<Vec<_>, _>>()?;
+                    handlers::insert_transactions(&state, &transactions).await?;
+                    transactions.len()
+                }
+            };
+            println!("Imported {count} transactions");
+        }
+        Command::Accounts => {
+            let Json(accounts) = handlers::get_account_totals(State(state)).await?;
+            let total = accounts.iter().fold(0.0, |total, a| total + a.amount);
+            for account in &accounts {
+                println!("{:<20} {:>12.2}", account.name, account.amount);
+            }
+            println!("{:<20} {:>12.2}", "Total", total);
+        }
+        Command::Budget { month } => {
+            let date = month.unwrap_or(today);
+            let Json(progress) =
+                handlers::budget_progress(Query(BudgetProgressOptions { date }), State(state))
+                    .await?;
+            println!(
+                "Spent {:.2} of {:.2} ({:.0}%)",
+                progress.spend.unwrap_or_default(),
+                progress.budget,
+                progress.progress() * 100.0
+            );
+        }
+        Command::Export {
+            format,
+            output,
+            filter,
+        } => {
+            let filter = TransactionFilter::from(filter);
+            let response = match format {
+                OutputFormat::Csv | OutputFormat::Json | OutputFormat::Excel => {
+                    let format = match format {
+                        OutputFormat::Json => ExportFormat::Json,
+                        OutputFormat::Excel => ExportFormat::Excel,
+                        _ => ExportFormat::Csv,
+                    };
+                    export::export_transactions(
+                        Query(ExportOptions { format, filter }),
+                        State(state),
+                    )
+                    .await?
+                    .into_response()
+                }
+                OutputFormat::Qif => qif::export_qif(Query(filter), State(state))
+                    .await?
+                    .into_response(),
+                OutputFormat::Journal => ledger::export_journal(Query(filter), State(state))
+                    .await?
+                    .into_response(),
+            };
+            let mut out: Box<dyn Write + Send> = match output {
+                Some(path) => Box::new(fs::File::create(path)?),
+                None => Box::new(io::stdout()),
+            };
+            write_body(response, &mut out).await?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/* Exports stream their body, so copy it out chunk by chunk */
+async fn write_body(response: Response, out: &mut (impl Write + Send)) -> anyhow::Result<()> {
+    let mut body = response.into_body();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| anyhow!("export failed: {e}"))?;
+        out.write_all(&chunk)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{boxed, Body, Bytes},
+    extract::State,
+    http::header,
+    response::IntoResponse,
+};
+use common::{ApiError, ExportFormat, ExportOptions, Transaction, TransactionFilter};
+use futures::StreamExt;
+use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqlitePool};
+
+use crate::{extract::Query, handlers::push_filter, AppState};
+
+/* Rows are encoded in batches of roughly this many bytes before being sent */
+const CHUNK_BYTES: usize = 64 * 1024;
+
+pub async fn export_transactions(
+    Query(opts): Query<ExportOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let ExportOptions { format, filter } = opts;
+    let pool = app_state.pool.clone();
+    let (mut sender, body) = Body::channel();
+
+    tokio::spawn(async move {
+        let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "SELECT rowid as id, account, date, description, amount, l1_tag, l2_tag, l3_tag, version
+            FROM finances WHERE deleted_at IS NULL",
+        );
+        push_filter(&mut query_builder, &filter);
+        query_builder.push(" ORDER BY date, rowid");
+        let mut rows = query_builder
+            .build()
+            .try_map(transaction_from_row)
+            .fetch(&pool);
+
+        let mut encoder = Encoder::new(format);
+        while let Some(row) = rows.next().await {
+            let transaction = match row {
+                Ok(t) => t,
+                Err(e) => {
+                    tracing::error!("export failed: {e}");
+                    sender.abort();
+                    return;
+                }
+            };
+            if let Err(e) = encoder.push(&transaction) {
+                tracing::error!("export failed: {e}");
+                sender.abort();
+                return;
+            }
+            if encoder.buffer.len() >= CHUNK_BYTES
+                && sender.send_data(encoder.take()).await.is_err()
+            {
+                /* The client has gone away */
+                return;
+            }
+        }
+        encoder.finish();
+        let _ = sender.send_data(encoder.take()).await;
+    });
+
+    let filename = format!(
+        "attachment; filename=\"transactions.{}\"",
+        format.extension()
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_owned()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        boxed(body),
+    ))
+}
+
+/* For formats written in one go rather than streamed */
+pub async fn fetch_transactions(
+    pool: &SqlitePool,
+    filter: &TransactionFilter,
+) -> Result<Vec<Transaction>, ApiError> {
+    let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT rowid as id, account, date, description, amount, l1_tag, l2_tag, l3_tag, version
+        FROM finances WHERE deleted_at IS NULL",
+    );
+    push_filter(&mut query_builder, filter);
+    query_builder.push(" ORDER BY date, rowid");
+    query_builder
+        .build()
+        .try_map(transaction_from_row)
+        .fetch_all(pool)
+        .await
+        .map_err(ApiError::internal)
+}
+
+fn transaction_from_row(row: SqliteRow) -> Result<Transaction, sqlx::Error> {
+    Ok(Transaction {
+        id: row.try_get("id")?,
+        account: row.try_get("account")?,
+        date: row.try_get("date")?,
+        description: row.try_get("description")?,
+        amount: row.try_get("amount")?,
+        l1_tag: row.try_get("l1_tag")?,
+        l2_tag: row.try_get("l2_tag")?,
+        l3_tag: row.try_get("l3_tag")?,
+        version: row.try_get("version")?,
+    })
+}
+
+struct Encoder {
+    format: ExportFormat,
+    buffer: Vec<u8>,
+    rows: usize,
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Self {
+        let buffer = match format {
+            ExportFormat::Csv => vec![],
+            ExportFormat::Json => b"[".to_vec(),
+            ExportFormat::Excel => "\u{feff}".as_bytes().to_vec(),
+        };
+        Self {
+            format,
+            buffer,
+            rows: 0,
+        }
+    }
+
+    fn push(&mut self, transaction: &Transaction) -> Result<(), String> {
+        match self.format {
+            ExportFormat::Json => {
+                if self.rows > 0 {
+                    self.buffer.push(b',');
+                }
+                serde_json::to_writer(&mut self.buffer, transaction).map_err(|e| e.to_string())?;
+            }
+            ExportFormat::Csv | ExportFormat::Excel => {
+                let terminator = match self.format {
+                    ExportFormat::Excel => csv::Terminator::CRLF,
+                    _ => csv::Terminator::Any(b'\n'),
+                };
+                /* A fresh writer per row, so only the first row writes the headers */
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(self.rows == 0)
+                    .terminator(terminator)
+                    .from_writer(&mut self.buffer);
+                writer.serialize(transaction).map_err(|e| e.to_string())?;
+                writer.flush().map_err(|e| e.to_string())?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        if self.format == ExportFormat::Json {
+            self.buffer.push(b']');
+        }
+    }
+
+    fn take(&mut self) -> Bytes {
+        Bytes::from(std::mem::take(&mut self.buffer))
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use axum::Router;
+use common::ApiError;
+use tower_http::services::{ServeDir, ServeFile};
+
+#[cfg(not(feature = "embed-frontend"))]
+const DEFAULT_DIR: &str = "frontend/dist";
+const INDEX: &str = "index.html";
+
+/* Anything that isn't an API route is a frontend asset. Unknown paths get
+ * index.html so yew_router can handle routes such as /balance on reload.
+ * A configured directory wins over assets embedded at build time */
+pub fn serve(app: Router, static_dir: Option<&Path>) -> Router {
+    match static_dir {
+        Some(dir) => serve_dir(app, dir),
+        None => serve_default(app),
+    }
+}
+
+/* Unknown API paths shouldn't get the frontend */
+pub async fn api_not_found() -> ApiError {
+    ApiError::not_found("No such API endpoint.")
+}
+
+fn serve_dir(app: Router, dir: &Path) -> Router {
+    tracing::info!("serving frontend from {}", dir.display());
+    app.fallback_service(ServeDir::new(dir).fallback(ServeFile::new(dir.join(INDEX))))
+}
+
+#[cfg(not(feature = "embed-frontend"))]
+fn serve_default(app: Router) -> Router {
+    serve_dir(app, Path::new(DEFAULT_DIR))
+}
+
+#[cfg(feature = "embed-frontend")]
+fn serve_default(app: Router) -> Router {
+    tracing::info!("serving embedded frontend");
+    app.fallback(embedded::asset)
+}
+
+#[cfg(feature = "embed-frontend")]
+mod embedded {
+    use axum::{
+        http::{header, StatusCode, Uri},
+        response::{IntoResponse, Response},
+    };
+    use rust_embed::RustEmbed;
+
+    use super::INDEX;
+
+    /* Run trunk build in frontend before building with this feature */
+    #[derive(RustEmbed)]
+    #[folder = "../frontend/dist"]
+    struct Assets;
+
+    pub async fn asset(uri: Uri) -> Response {
+        let path = uri.path().trim_start_matches('/');
+        let (path, file) = match Assets::get(path) {
+            Some(file) => (path, file),
+            None => match Assets::get(INDEX) {
+                Some(file) => (INDEX, file),
+                None => return StatusCode::NOT_FOUND.into_response(),
+            },
+        };
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+        ([(header::CONTENT_TYPE, mime.to_string())], file.data).into_response()
+    }
+}
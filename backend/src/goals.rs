@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode};
+use chrono::{NaiveDate, Utc};
+use common::{ApiError, Goal, GoalLink, GoalProgress};
+use sqlx::{pool::PoolConnection, Sqlite};
+
+use crate::{extract::Json, AppState};
+
+pub async fn list_goals(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<GoalProgress>>, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let rows = sqlx::query!(
+        r#"SELECT id as "id!", name, target, target_date as "target_date!: NaiveDate", account, tag
+        FROM goals ORDER BY target_date, name"#
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let today = Utc::now().date_naive();
+    let mut goals = vec![];
+    for row in rows {
+        let link = match (row.account, row.tag) {
+            (Some(account), _) => GoalLink::Account(account),
+            (None, Some(tag)) => GoalLink::Tag(tag),
+            (None, None) => return Err(ApiError::internal("goal has no account or tag")),
+        };
+        let goal = Goal {
+            id: row.id,
+            name: row.name,
+            target: row.target,
+            target_date: row.target_date,
+            link,
+        };
+        let saved = saved_towards(&mut conn, &goal.link).await?;
+        goals.push(GoalProgress::new(goal, saved, today));
+    }
+
+    Ok(Json(goals))
+}
+
+/* Account goals track the balance, tag goals the total moved under that tag */
+async fn saved_towards(
+    conn: &mut PoolConnection<Sqlite>,
+    link: &GoalLink,
+) -> Result<f64, ApiError> {
+    let saved = match link {
+        GoalLink::Account(account) => {
+            sqlx::query_scalar!(
+                r#"SELECT TOTAL(amount) as "saved!: f64" FROM finances
+            WHERE deleted_at IS NULL AND account = ?1"#,
+                account
+            )
+            .fetch_one(conn)
+            .await
+        }
+        GoalLink::Tag(tag) => {
+            sqlx::query_scalar!(
+                r#"SELECT ABS(TOTAL(amount)) as "saved!: f64" FROM finances
+            WHERE deleted_at IS NULL AND (l1_tag = ?1 OR l2_tag = ?1 OR l3_tag = ?1)"#,
+                tag
+            )
+            .fetch_one(conn)
+            .await
+        }
+    };
+    saved.map_err(ApiError::internal)
+}
+
+pub async fn create_goal(
+    State(app_state): State<Arc<AppState>>,
+    Json(goal): Json<Goal>,
+) -> Result<(StatusCode, Json<i64>), ApiError> {
+    app_state.config_db.lock().await.validate_goal(&goal)?;
+
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let (account, tag) = match goal.link {
+        GoalLink::Account(account) => (Some(account), None),
+        GoalLink::Tag(tag) => (None, Some(tag)),
+    };
+
+    let id = sqlx::query!(
+        r#"
+        INSERT INTO goals ( name, target, target_date, account, tag )
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        goal.name,
+        goal.target,
+        goal.target_date,
+        account,
+        tag
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(ApiError::internal)?
+    .last_insert_rowid();
+
+    Ok((StatusCode::CREATED, Json(id)))
+}
+
+pub async fn delete_goal(
+    State(app_state): State<Arc<AppState>>,
+    Json(id): Json<i64>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let result = sqlx::query!("DELETE FROM goals WHERE id = ?1", id)
+        .execute(&mut conn)
+        .await
+        .map_err(ApiError::internal)?;
+
+    match result.rows_affected() {
+        0 => Err(ApiError::not_found(format!("No goal {id}."))),
+        _ => Ok(StatusCode::OK),
+    }
+}
@@ -1,46 +1,104 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
+use axum::{extract::State, http::StatusCode};
+use chrono::{Duration, NaiveDateTime, Utc};
 use common::{
-    AccountSummary, BalanceByTime, BalanceTimeOptions, BudgetProgress, BudgetProgressOptions,
-    CategorySpend, CategorySpendOptions, Config, ConfigOptions, DateGrouping, ListOptions,
-    Transaction,
+    AccountSummary, ApiError, BalanceByTime, BalanceOverTime, BalanceTimeOptions, BudgetProgress,
+    BudgetProgressOptions, BulkEditRequest, BulkSelection, CategoryComparison, CategorySpend,
+    CategorySpendOptions, ComparisonOptions, Config, ConfigOptions, DailyTotal, DateGrouping, Flow,
+    FlowOptions, Forecast, ForecastOptions, KpiSummary, ListOptions, TagLevel, Transaction,
+    TransactionFilter, TransactionPatch,
+};
+use sqlx::{QueryBuilder, Row, Sqlite, SqliteConnection};
+
+use crate::{
+    extract::{Json, Path, Query},
+    AppState,
 };
-use sqlx::{QueryBuilder, Row, Sqlite};
 
-use crate::AppState;
+/* Money moving between our own accounts isn't income or spending */
+pub const PERIOD_EXCLUSIONS: &str = r#"l1_tag NOT IN ("Transfers", "Balance", "Repayments")"#;
+const FORECAST_LOOKBACK_DAYS: i64 = 180;
+const KPI_LOOKBACK_DAYS: i64 = 400;
+const MAX_FORECAST_DAYS: i64 = 730;
 
 pub async fn list_transactions(
     Query(opts): Query<ListOptions>,
     State(app_state): State<Arc<AppState>>,
-) -> Json<Vec<Transaction>> {
+) -> Result<Json<Vec<Transaction>>, ApiError> {
     let pool = app_state.pool.clone();
     let limit = opts.limit.unwrap_or(50) as i64;
     let offset = opts.offset.unwrap_or(0) as i64;
     let transactions = sqlx::query_as!(
         Transaction,
         r#"SELECT rowid as "id!", account as "account!", date as "date!", description as "description!",
-        amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!"
-        FROM finances ORDER BY date DESC LIMIT ? OFFSET ?"#,
+        amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!",
+        version as "version!"
+        FROM finances WHERE deleted_at IS NULL ORDER BY date DESC LIMIT ? OFFSET ?"#,
         limit,
         offset
     )
     .fetch_all(&pool)
     .await
-    .unwrap();
-    Json(transactions)
+    .map_err(ApiError::internal)?;
+    Ok(Json(transactions))
+}
+
+/* All or nothing, a file with any invalid transaction imports none of them */
+pub async fn insert_transactions(
+    app_state: &AppState,
+    transactions: &[Transaction],
+) -> Result<(), ApiError> {
+    {
+        let config = app_state.config_db.lock().await;
+        for transaction in transactions {
+            config.validate_transaction(transaction)?;
+        }
+    }
+
+    let mut tx = app_state.pool.begin().await.map_err(ApiError::internal)?;
+    for Transaction {
+        account,
+        date,
+        description,
+        amount,
+        l1_tag,
+        l2_tag,
+        l3_tag,
+        ..
+    } in transactions
+    {
+        sqlx::query!(
+            r#"
+            INSERT INTO finances ( account, date, description, amount, l1_tag, l2_tag, l3_tag)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            account,
+            date,
+            description,
+            amount,
+            l1_tag,
+            l2_tag,
+            l3_tag
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(ApiError::internal)?;
+    }
+    tx.commit().await.map_err(ApiError::internal)
 }
 
 pub async fn create_transaction(
     State(app_state): State<Arc<AppState>>,
     Json(transaction): Json<Transaction>,
-) -> impl IntoResponse {
-    let mut conn = app_state.pool.acquire().await.unwrap();
+) -> Result<(StatusCode, Json<i64>), ApiError> {
+    app_state
+        .config_db
+        .lock()
+        .await
+        .validate_transaction(&transaction)?;
+
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
     let Transaction {
         id: _,
         account,
@@ -50,6 +108,7 @@ pub async fn create_transaction(
         l1_tag,
         l2_tag,
         l3_tag,
+        version: _,
     } = transaction;
 
     let id = sqlx::query!(
@@ -67,21 +126,49 @@ pub async fn create_transaction(
     )
     .execute(&mut conn)
     .await
-    .unwrap()
+    .map_err(ApiError::internal)?
     .last_insert_rowid();
 
-    (StatusCode::CREATED, Json(id))
+    Ok((StatusCode::CREATED, Json(id)))
 }
 
 #[axum::debug_handler]
 pub async fn update_transaction(
     State(app_state): State<Arc<AppState>>,
-    Json(patch_transaction): Json<Transaction>,
-) -> Result<StatusCode, StatusCode> {
-    let mut conn = app_state.pool.acquire().await.unwrap();
+    Json(patch): Json<TransactionPatch>,
+) -> Result<Json<i64>, ApiError> {
+    let mut tx = app_state.pool.begin().await.map_err(ApiError::internal)?;
 
-    let Transaction {
+    let current = sqlx::query_as!(
+        Transaction,
+        r#"SELECT rowid as "id!", account as "account!", date as "date!", description as "description!",
+        amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!",
+        version as "version!"
+        FROM finances WHERE rowid = ?1 AND deleted_at IS NULL"#,
+        patch.id
+    )
+    .fetch_optional(&mut tx)
+    .await
+    .map_err(ApiError::internal)?;
+    let Some(mut updated) = current else {
+        return Err(ApiError::not_found(format!("No transaction {}.", patch.id)));
+    };
+    if updated.version != patch.version {
+        return Err(stale_version(patch.id, patch.version));
+    }
+    if patch.account.is_some() || patch.date.is_some() || patch.amount.is_some() {
+        reject_trade(&mut tx, patch.id, EDITED).await?;
+    }
+    patch.apply(&mut updated);
+    app_state
+        .config_db
+        .lock()
+        .await
+        .validate_transaction(&updated)?;
+
+    let TransactionPatch {
         id,
+        version,
         account,
         date,
         description,
@@ -89,54 +176,250 @@ pub async fn update_transaction(
         l1_tag,
         l2_tag,
         l3_tag,
-    } = patch_transaction;
+    } = patch;
 
-    let result = sqlx::query!(
-        r#"
-        UPDATE finances
-        SET account = ?1, date = ?2, description = ?3, amount = ?4,
-        l1_tag = ?5, l2_tag = ?6, l3_tag = ?7
-        WHERE rowid = ?8
-        "#,
-        account,
-        date,
-        description,
-        amount,
-        l1_tag,
-        l2_tag,
-        l3_tag,
-        id
-    )
-    .execute(&mut conn)
-    .await;
+    let mut query_builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("UPDATE finances SET version = version + 1");
+    if let Some(account) = account {
+        query_builder.push(", account = ").push_bind(account);
+    }
+    if let Some(date) = date {
+        query_builder.push(", date = ").push_bind(date);
+    }
+    if let Some(description) = description {
+        query_builder
+            .push(", description = ")
+            .push_bind(description);
+    }
+    if let Some(amount) = amount {
+        query_builder.push(", amount = ").push_bind(amount);
+    }
+    if let Some(l1_tag) = l1_tag {
+        query_builder.push(", l1_tag = ").push_bind(l1_tag);
+    }
+    if let Some(l2_tag) = l2_tag {
+        query_builder.push(", l2_tag = ").push_bind(l2_tag);
+    }
+    if let Some(l3_tag) = l3_tag {
+        query_builder.push(", l3_tag = ").push_bind(l3_tag);
+    }
+    query_builder
+        .push(" WHERE deleted_at IS NULL AND rowid = ")
+        .push_bind(id)
+        .push(" AND version = ")
+        .push_bind(version);
+
+    let rows = query_builder
+        .build()
+        .execute(&mut tx)
+        .await
+        .map_err(ApiError::internal)?
+        .rows_affected();
+    if rows == 0 {
+        return Err(stale_version(id, version));
+    }
+    tx.commit().await.map_err(ApiError::internal)?;
+
+    Ok(Json(version + 1))
+}
 
-    match result {
-        Ok(_) => Ok(StatusCode::OK),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
+fn stale_version(id: i64, version: i64) -> ApiError {
+    ApiError::conflict(format!(
+        "Transaction {id} has been changed since version {version}."
+    ))
+}
+
+pub async fn bulk_update_transactions(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<BulkEditRequest>,
+) -> Result<Json<u64>, ApiError> {
+    let BulkEditRequest { selection, update } = request;
+    if update.is_empty() {
+        return Err(ApiError::bad_request("Nothing to update."));
+    }
+    app_state
+        .config_db
+        .lock()
+        .await
+        .validate_bulk_update(&update)?;
+    let selection = match selection {
+        BulkSelection::Ids(mut ids) => {
+            ids.sort_unstable();
+            ids.dedup();
+            if ids.is_empty() {
+                return Err(ApiError::bad_request("No transactions selected."));
+            }
+            BulkSelection::Ids(ids)
+        }
+        BulkSelection::Filter(filter) => {
+            if filter.is_empty() {
+                return Err(ApiError::bad_request(
+                    "Refusing to update every transaction.",
+                ));
+            }
+            BulkSelection::Filter(filter)
+        }
+    };
+    let mut tx = app_state.pool.begin().await.map_err(ApiError::internal)?;
+
+    if update.account.is_some() || update.date_shift.is_some() {
+        let mut trades: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT rowid FROM finances");
+        push_selection(&mut trades, &selection);
+        trades.push(" AND rowid IN (SELECT finance_id FROM trades) LIMIT 1");
+        let trade = trades
+            .build()
+            .fetch_optional(&mut tx)
+            .await
+            .map_err(ApiError::internal)?;
+        if let Some(row) = trade {
+            return Err(trade_conflict(row.get(0), EDITED));
+        }
+    }
+    if update.date_shift.is_some() {
+        let mut dates: QueryBuilder<Sqlite> =
+            QueryBuilder::new("SELECT MIN(date), MAX(date) FROM finances");
+        push_selection(&mut dates, &selection);
+        let row = dates
+            .build()
+            .fetch_one(&mut tx)
+            .await
+            .map_err(ApiError::internal)?;
+        let first: Option<NaiveDateTime> = row.try_get(0).map_err(ApiError::internal)?;
+        let last: Option<NaiveDateTime> = row.try_get(1).map_err(ApiError::internal)?;
+        if let (Some(first), Some(last)) = (first, last) {
+            if let Err(e) = update.validate_shifted_dates(first.date(), last.date()) {
+                tx.rollback().await.map_err(ApiError::internal)?;
+                return Err(e);
+            }
+        }
+    }
+
+    let mut query_builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("UPDATE finances SET version = version + 1");
+    if let Some(account) = update.account {
+        query_builder.push(", account = ").push_bind(account);
+    }
+    if let Some((l1_tag, l2_tag, l3_tag)) = update.tags {
+        query_builder.push(", l1_tag = ").push_bind(l1_tag);
+        query_builder.push(", l2_tag = ").push_bind(l2_tag);
+        query_builder.push(", l3_tag = ").push_bind(l3_tag);
+    }
+    if let Some(prefix) = update.description_prefix {
+        query_builder
+            .push(", description = ")
+            .push_bind(prefix)
+            .push(" || description");
+    }
+    if let Some(days) = update.date_shift {
+        query_builder
+            .push(", date = DATETIME(date, ")
+            .push_bind(format!("{days:+} days"))
+            .push(")");
+    }
+
+    push_selection(&mut query_builder, &selection);
+    let expected_rows = match &selection {
+        BulkSelection::Ids(ids) => Some(ids.len() as u64),
+        BulkSelection::Filter(_) => None,
+    };
+
+    let rows = query_builder
+        .build()
+        .execute(&mut tx)
+        .await
+        .map_err(ApiError::internal)?
+        .rows_affected();
+    if let Some(expected) = expected_rows.filter(|&expected| expected != rows) {
+        tx.rollback().await.map_err(ApiError::internal)?;
+        return Err(ApiError::not_found(format!(
+            "Only {rows} of {expected} selected transactions exist."
+        )));
+    }
+    tx.commit().await.map_err(ApiError::internal)?;
+
+    Ok(Json(rows))
+}
+
+fn push_selection(query_builder: &mut QueryBuilder<Sqlite>, selection: &BulkSelection) {
+    query_builder.push(" WHERE deleted_at IS NULL");
+    match selection {
+        BulkSelection::Ids(ids) => {
+            query_builder.push(" AND rowid IN (");
+            let mut separated = query_builder.separated(", ");
+            for id in ids.iter() {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+        }
+        BulkSelection::Filter(filter) => push_filter(query_builder, filter),
+    }
+}
+
+pub fn push_filter(query_builder: &mut QueryBuilder<Sqlite>, filter: &TransactionFilter) {
+    if let Some(account) = &filter.account {
+        query_builder
+            .push(" AND account = ")
+            .push_bind(account.clone());
+    }
+    if let Some(description) = &filter.description {
+        query_builder
+            .push(" AND description LIKE '%' || ")
+            .push_bind(description.clone())
+            .push(" || '%'");
+    }
+    if let Some(l1_tag) = &filter.l1_tag {
+        query_builder
+            .push(" AND l1_tag = ")
+            .push_bind(l1_tag.clone());
+    }
+    if let Some(l2_tag) = &filter.l2_tag {
+        query_builder
+            .push(" AND l2_tag = ")
+            .push_bind(l2_tag.clone());
+    }
+    if let Some(l3_tag) = &filter.l3_tag {
+        query_builder
+            .push(" AND l3_tag = ")
+            .push_bind(l3_tag.clone());
+    }
+    if let Some(from) = filter.from {
+        query_builder.push(" AND DATE(date) >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        query_builder.push(" AND DATE(date) <= ").push_bind(to);
     }
 }
 
 pub async fn get_account_totals(
     State(app_state): State<Arc<AppState>>,
-) -> Json<Vec<AccountSummary>> {
+) -> Result<Json<Vec<AccountSummary>>, ApiError> {
     let pool = app_state.pool.clone();
     let accounts = sqlx::query_as!(
         AccountSummary,
-        r#"WITH grouped AS
-        (SELECT account as name, SUM(amount) as amount FROM finances GROUP BY account ORDER BY name)
-        SELECT name, amount as "amount!" FROM grouped WHERE abs(amount) > 0.001"#
+        r#"WITH latest AS
+        (SELECT ticker, price FROM prices p
+        WHERE date = (SELECT MAX(date) FROM prices WHERE ticker = p.ticker)),
+        valued AS
+        (SELECT h.account, COALESCE(h.quantity * l.price, h.cost_basis) as amount
+        FROM holdings h LEFT JOIN latest l ON l.ticker = h.ticker WHERE h.quantity > 0),
+        grouped AS
+        (SELECT account as name, SUM(amount) as amount FROM
+        (SELECT account, amount FROM finances WHERE deleted_at IS NULL
+        UNION ALL SELECT account, amount FROM valued)
+        GROUP BY account ORDER BY name)
+        SELECT name as "name!: String", amount as "amount!: f64" FROM grouped WHERE abs(amount) > 0.001"#
     )
     .fetch_all(&pool)
     .await
-    .unwrap();
+    .map_err(ApiError::internal)?;
 
-    Json(accounts)
+    Ok(Json(accounts))
 }
 
 pub async fn get_config(
     Path(key): Path<String>,
     State(app_state): State<Arc<AppState>>,
-) -> Result<Json<ConfigOptions>, StatusCode> {
+) -> Result<Json<ConfigOptions>, ApiError> {
     let config = app_state.config_db.lock().await;
     let config: Config = config.clone();
     let option = match key.as_str() {
@@ -146,7 +429,8 @@ pub async fn get_config(
         "period_items" => ConfigOptions::PeriodItems(config.period_items().to_owned()),
         "budget_items" => ConfigOptions::BudgetItems(config.budget_items().to_owned()),
         "tags" => ConfigOptions::Tags(config.tags().to_owned()),
-        _ => return Err(StatusCode::NOT_FOUND),
+        "period_start" => ConfigOptions::PeriodStart(config.period_start()),
+        _ => return Err(ApiError::not_found(format!("No config option {key:?}."))),
     };
 
     Ok(Json(option))
@@ -155,119 +439,436 @@ pub async fn get_config(
 pub async fn delete_transaction(
     State(app_state): State<Arc<AppState>>,
     Json(id): Json<i64>,
-) -> StatusCode {
-    let mut conn = app_state.pool.acquire().await.unwrap();
+) -> Result<StatusCode, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    reject_trade(&mut conn, id, "deleted").await?;
     let result = sqlx::query!(
         r#"
-        DELETE FROM finances WHERE rowid = ?1
+        UPDATE finances SET deleted_at = CURRENT_TIMESTAMP
+        WHERE rowid = ?1 AND deleted_at IS NULL
         "#,
         id
     )
     .execute(&mut conn)
-    .await;
+    .await
+    .map_err(ApiError::internal)?;
 
-    match result {
-        Ok(_) => StatusCode::OK,
-        Err(_) => StatusCode::NOT_FOUND,
+    match result.rows_affected() {
+        0 => Err(ApiError::not_found(format!("No transaction {id}."))),
+        _ => Ok(StatusCode::OK),
     }
 }
 
+pub async fn list_trash(
+    Query(opts): Query<ListOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Transaction>>, ApiError> {
+    let pool = app_state.pool.clone();
+    let limit = opts.limit.unwrap_or(50) as i64;
+    let offset = opts.offset.unwrap_or(0) as i64;
+    let transactions = sqlx::query_as!(
+        Transaction,
+        r#"SELECT rowid as "id!", account as "account!", date as "date!", description as "description!",
+        amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!",
+        version as "version!"
+        FROM finances WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC LIMIT ? OFFSET ?"#,
+        limit,
+        offset
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::internal)?;
+    Ok(Json(transactions))
+}
+
+pub async fn restore_transaction(
+    State(app_state): State<Arc<AppState>>,
+    Json(id): Json<i64>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE finances SET deleted_at = NULL
+        WHERE rowid = ?1 AND deleted_at IS NOT NULL
+        "#,
+        id
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(ApiError::internal)?;
+
+    match result.rows_affected() {
+        0 => Err(ApiError::not_found(format!("No transaction {id}."))),
+        _ => Ok(StatusCode::OK),
+    }
+}
+
+pub async fn purge_transaction(
+    State(app_state): State<Arc<AppState>>,
+    Json(id): Json<i64>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    reject_trade(&mut conn, id, "deleted").await?;
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM finances WHERE rowid = ?1 AND deleted_at IS NOT NULL
+        "#,
+        id
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(ApiError::internal)?;
+
+    match result.rows_affected() {
+        0 => Err(ApiError::not_found(format!("No transaction {id}."))),
+        _ => Ok(StatusCode::OK),
+    }
+}
+
+/* A trade's holding is kept in step with its finances row, removing the row
+ * or moving its money alone would leave the holding as it was */
+const EDITED: &str = "given a new account, date or amount";
+
+async fn reject_trade(conn: &mut SqliteConnection, id: i64, action: &str) -> Result<(), ApiError> {
+    let trades = sqlx::query_scalar!("SELECT COUNT(*) FROM trades WHERE finance_id = ?1", id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(ApiError::internal)?;
+    if trades > 0 {
+        return Err(trade_conflict(id, action));
+    }
+    Ok(())
+}
+
+fn trade_conflict(id: i64, action: &str) -> ApiError {
+    ApiError::conflict(format!(
+        "Transaction {id} records a trade and can't be {action}."
+    ))
+}
+
 pub async fn balance_by_date(
     Query(opts): Query<BalanceTimeOptions>,
     State(app_state): State<Arc<AppState>>,
-) -> Json<Vec<BalanceByTime>> {
+) -> Result<Json<Vec<BalanceByTime>>, ApiError> {
     let pool = app_state.pool.clone();
-    let balance = match opts.grouping.unwrap_or(DateGrouping::Day) {
-        DateGrouping::Day => sqlx::query_as!(
-            BalanceByTime,
-            r#"SELECT STRFTIME("%Y-%m-%d", date) as "date!",
-            SUM(CASE WHEN amount >= 0 THEN amount END) as "incoming!",
-            SUM(CASE WHEN amount < 0 THEN amount END) as "outgoing!",
-            SUM(amount) as "balance!"
-        FROM finances WHERE l1_tag != "Repayments" AND l2_tag != "Transfers"
-        GROUP BY STRFTIME("%Y-%m-%d", date)
-        "#
-        )
-        .fetch_all(&pool)
-        .await
-        .unwrap(),
-        DateGrouping::Month => sqlx::query_as!(
-            BalanceByTime,
-            r#"SELECT STRFTIME("%Y-%m", date) as "date!",
-            SUM(CASE WHEN amount >= 0 THEN amount END) as "incoming!",
-            SUM(CASE WHEN amount < 0 THEN amount END) as "outgoing!",
-            SUM(amount) as "balance!"
-        FROM finances WHERE l1_tag not in ("Transfers", "Balance", "Repayments")
-        GROUP BY STRFTIME("%Y-%m", date)
-        "#
-        )
+    let grouping = opts.grouping.unwrap_or_default();
+    let group = date_grouping_expression(grouping);
+
+    /* Daily figures feed the running balance, so opening balances are kept */
+    let filter = match grouping {
+        DateGrouping::Day => r#"l1_tag != "Repayments" AND l2_tag != "Transfers""#,
+        _ => PERIOD_EXCLUSIONS,
+    };
+
+    let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        r#"SELECT {group} as date,
+            TOTAL(CASE WHEN amount >= 0 THEN amount END) as incoming,
+            TOTAL(CASE WHEN amount < 0 THEN amount END) as outgoing,
+            TOTAL(amount) as balance
+        FROM finances WHERE deleted_at IS NULL AND {filter}"#
+    ));
+    if let Some(from) = opts.from {
+        query_builder.push(" AND DATE(date) >= ").push_bind(from);
+    }
+    if let Some(to) = opts.to {
+        query_builder.push(" AND DATE(date) <= ").push_bind(to);
+    }
+    query_builder.push(format!(" GROUP BY {group} ORDER BY {group}"));
+
+    let balance = query_builder
+        .build()
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            Ok(BalanceByTime {
+                date: row.try_get("date")?,
+                incoming: row.try_get("incoming")?,
+                outgoing: row.try_get("outgoing")?,
+                balance: row.try_get("balance")?,
+            })
+        })
         .fetch_all(&pool)
         .await
-        .unwrap(),
-    };
-    Json(balance)
+        .map_err(ApiError::internal)?;
+    Ok(Json(balance))
+}
+
+const fn date_grouping_expression(grouping: DateGrouping) -> &'static str {
+    match grouping {
+        DateGrouping::Day => r#"STRFTIME("%Y-%m-%d", date)"#,
+        /* Weeks are labelled by the Monday they start on */
+        DateGrouping::Week => r#"DATE(date, "weekday 0", "-6 days")"#,
+        DateGrouping::Month => r#"STRFTIME("%Y-%m", date)"#,
+        DateGrouping::Quarter => {
+            r#"STRFTIME("%Y-Q", date) || ((CAST(STRFTIME("%m", date) AS INTEGER) + 2) / 3)"#
+        }
+        DateGrouping::Year => r#"STRFTIME("%Y", date)"#,
+    }
 }
 
 pub async fn budget_progress(
     Query(opts): Query<BudgetProgressOptions>,
     State(app_state): State<Arc<AppState>>,
-) -> Json<BudgetProgress> {
+) -> Result<Json<BudgetProgress>, ApiError> {
     let pool = app_state.pool.clone();
     let config = app_state.config_db.lock().await;
     let budget = config.budget();
     let budget_items = config.budget_items();
-
-    let date = opts.date.format("%Y-%m").to_string();
+    let (start, end) = config.period_start().period(opts.date);
 
     let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(
         r#"
         SELECT ABS(SUM(amount)) as spend
-        FROM finances WHERE l1_tag in ("#,
+        FROM finances WHERE deleted_at IS NULL AND l1_tag in ("#,
     );
     let mut separated = query_builder.separated(", ");
     for tag in budget_items.iter() {
         separated.push_bind(tag);
     }
-    separated.push_unseparated(r#") AND STRFTIME("%Y-%m", date) = "#);
-    separated.push_unseparated(format!("\"{date}\""));
+    separated.push_unseparated(") AND DATE(date) >= ");
+    separated.push_bind_unseparated(start);
+    separated.push_unseparated(" AND DATE(date) < ");
+    separated.push_bind_unseparated(end);
 
     let query = query_builder.build();
-    let row = query.fetch_one(&pool).await.unwrap();
+    let row = query.fetch_one(&pool).await.map_err(ApiError::internal)?;
 
-    Json(BudgetProgress {
+    Ok(Json(BudgetProgress {
         budget,
-        spend: row.try_get("spend").unwrap(),
-    })
+        spend: row.try_get("spend").map_err(ApiError::internal)?,
+    }))
 }
 
 pub async fn category_spend(
     Query(opts): Query<CategorySpendOptions>,
     State(app_state): State<Arc<AppState>>,
-) -> Json<Vec<CategorySpend>> {
+) -> Result<Json<Vec<CategorySpend>>, ApiError> {
     let pool = app_state.pool.clone();
+    let period_start = app_state.config_db.lock().await.period_start();
+    let (start, end) = period_start.period(opts.date);
 
-    let date = opts.date.format("%Y-%m").to_string();
+    if opts.parent.len() != opts.level.depth() {
+        return Err(ApiError::bad_request(format!(
+            "{:?} spend needs {} parent tags, got {}.",
+            opts.level,
+            opts.level.depth(),
+            opts.parent.len()
+        )));
+    }
+    let column = tag_column(opts.level);
 
-    let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+    let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
         r#"
-        SELECT l1_tag, ABS(SUM(amount)) as spend
-        FROM finances WHERE l1_tag in ("#,
-    );
+        SELECT {column} as tag, ABS(SUM(amount)) as spend
+        FROM finances WHERE deleted_at IS NULL AND l1_tag in ("#
+    ));
     let mut separated = query_builder.separated(", ");
     for tag in opts.l1_tags.iter() {
         separated.push_bind(tag);
     }
-    separated.push_unseparated(r#") AND STRFTIME("%Y-%m", date) = "#);
-    separated.push_unseparated(format!("\"{date}\" GROUP BY l1_tag"));
+    separated.push_unseparated(") AND DATE(date) >= ");
+    separated.push_bind_unseparated(start);
+    separated.push_unseparated(" AND DATE(date) < ");
+    separated.push_bind_unseparated(end);
+    for (level, tag) in [TagLevel::L1, TagLevel::L2].into_iter().zip(&opts.parent) {
+        query_builder
+            .push(format!(" AND {} = ", tag_column(level)))
+            .push_bind(tag);
+    }
+    query_builder.push(format!(" GROUP BY {column}"));
 
     let query = query_builder.build();
     let rows = query
-        .map(|row: sqlx::sqlite::SqliteRow| CategorySpend {
-            name: row.try_get("l1_tag").unwrap(),
-            amount: row.try_get("spend").unwrap(),
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            Ok(CategorySpend {
+                name: row.try_get("tag")?,
+                amount: row.try_get("spend")?,
+            })
         })
         .fetch_all(&pool)
         .await
-        .unwrap();
-    Json(rows)
+        .map_err(ApiError::internal)?;
+    Ok(Json(rows))
+}
+
+const fn tag_column(level: TagLevel) -> &'static str {
+    match level {
+        TagLevel::L1 => "l1_tag",
+        TagLevel::L2 => "l2_tag",
+        TagLevel::L3 => "l3_tag",
+    }
+}
+
+pub async fn compare_category_spend(
+    Query(opts): Query<ComparisonOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CategoryComparison>>, ApiError> {
+    opts.validate()?;
+    let pool = app_state.pool.clone();
+
+    let mut query_builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT l1_tag, ABS(TOTAL(CASE WHEN DATE(date) BETWEEN ");
+    query_builder
+        .push_bind(opts.first_from)
+        .push(" AND ")
+        .push_bind(opts.first_to)
+        .push(" THEN amount END)) as first, ABS(TOTAL(CASE WHEN DATE(date) BETWEEN ")
+        .push_bind(opts.second_from)
+        .push(" AND ")
+        .push_bind(opts.second_to)
+        .push(
+            " THEN amount END)) as second FROM finances WHERE deleted_at IS NULL AND l1_tag in (",
+        );
+    let mut separated = query_builder.separated(", ");
+    for tag in opts.l1_tags.iter() {
+        separated.push_bind(tag);
+    }
+    separated.push_unseparated(") GROUP BY l1_tag ORDER BY l1_tag");
+
+    let query = query_builder.build();
+    let rows = query
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            Ok(CategoryComparison::new(
+                row.try_get("l1_tag")?,
+                row.try_get("first")?,
+                row.try_get("second")?,
+            ))
+        })
+        .fetch_all(&pool)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(Json(rows))
+}
+
+pub async fn income_flows(
+    Query(opts): Query<FlowOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Flow>>, ApiError> {
+    let pool = app_state.pool.clone();
+
+    let totals = sqlx::query!(
+        r#"SELECT l1_tag, l2_tag, SUM(amount) as "amount!: f64"
+        FROM finances WHERE l1_tag NOT IN ("Transfers", "Balance", "Repayments")
+        AND deleted_at IS NULL AND DATE(date) BETWEEN ?1 AND ?2
+        GROUP BY l1_tag, l2_tag ORDER BY l1_tag, l2_tag"#,
+        opts.from,
+        opts.to
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let flow = |source: &str, target: &str, amount: f64| Flow {
+        source: source.to_owned(),
+        target: target.to_owned(),
+        amount,
+    };
+
+    /* Tags that net positive are income sources, the rest is spending by l1 tag */
+    let mut flows = vec![];
+    let mut spending: BTreeMap<String, f64> = BTreeMap::new();
+    let mut income = 0.0;
+    for row in totals {
+        if row.amount >= 0.0 {
+            income += row.amount;
+            /* Untagged sources are named after l1, unless that is the hub itself */
+            let source = match (row.l2_tag.as_str(), row.l1_tag.as_str()) {
+                ("", "" | "Income") => "Other",
+                ("", l1_tag) => l1_tag,
+                (l2_tag, _) => l2_tag,
+            };
+            flows.push(flow(source, "Income", row.amount));
+        } else {
+            *spending.entry(row.l1_tag).or_default() -= row.amount;
+        }
+    }
+    let spend: f64 = spending.values().sum();
+    for (category, amount) in &spending {
+        flows.push(flow("Income", category, *amount));
+    }
+
+    /* Balance the hub so what comes in matches what goes out */
+    if income > spend {
+        flows.push(flow("Income", "Saved", income - spend));
+    } else if spend > income {
+        flows.push(flow("From savings", "Income", spend - income));
+    }
+
+    Ok(Json(flows))
+}
+
+pub async fn forecast_balance(
+    Query(opts): Query<ForecastOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<BalanceOverTime>, ApiError> {
+    if !(1..=MAX_FORECAST_DAYS).contains(&opts.days) {
+        return Err(ApiError::bad_request(format!(
+            "Forecast days must be between 1 and {MAX_FORECAST_DAYS}."
+        )));
+    }
+    let pool = app_state.pool.clone();
+    let today = Utc::now().date_naive();
+    let from = today - Duration::days(FORECAST_LOOKBACK_DAYS);
+
+    let balance = sqlx::query!(
+        r#"SELECT TOTAL(amount) as "balance!: f64"
+        FROM finances WHERE l1_tag != "Repayments" AND l2_tag != "Transfers"
+        AND deleted_at IS NULL AND DATE(date) <= ?1"#,
+        today
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(ApiError::internal)?
+    .balance;
+
+    let history = sqlx::query_as!(
+        Transaction,
+        r#"SELECT rowid as "id!", account as "account!", date as "date!", description as "description!",
+        amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!",
+        version as "version!"
+        FROM finances WHERE l1_tag NOT IN ("Transfers", "Balance", "Repayments")
+        AND deleted_at IS NULL AND DATE(date) > ?1 AND DATE(date) <= ?2"#,
+        from,
+        today
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let forecast = Forecast::from_history(&history, from, today);
+    Ok(Json(forecast.project(today, balance, opts.days)))
+}
+
+pub async fn kpis(State(app_state): State<Arc<AppState>>) -> Result<Json<KpiSummary>, ApiError> {
+    let pool = app_state.pool.clone();
+    let period_start = app_state.config_db.lock().await.period_start();
+    let today = Utc::now().date_naive();
+    /* A year of periods starts no earlier than this */
+    let from = today - Duration::days(KPI_LOOKBACK_DAYS);
+
+    let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        r#"SELECT DATE(date) as day,
+            TOTAL(CASE WHEN amount >= 0 THEN amount END) as income,
+            -TOTAL(CASE WHEN amount < 0 THEN amount END) as expense
+        FROM finances WHERE deleted_at IS NULL AND {PERIOD_EXCLUSIONS}"#
+    ));
+    query_builder
+        .push(" AND DATE(date) >= ")
+        .push_bind(from)
+        .push(" AND DATE(date) <= ")
+        .push_bind(today)
+        .push(" GROUP BY DATE(date)");
+
+    let days = query_builder
+        .build()
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            Ok(DailyTotal {
+                date: row.try_get("day")?,
+                income: row.try_get("income")?,
+                expense: row.try_get("expense")?,
+            })
+        })
+        .fetch_all(&pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(KpiSummary::new(period_start, today, &days)))
 }
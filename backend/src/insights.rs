@@ -0,0 +1,254 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::extract::State;
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc};
+use common::{Anomaly, AnomalyKind, ApiError, Transaction};
+
+use crate::{extract::Json, AppState};
+
+/* Whole months before this one, so averages aren't skewed by a partial month */
+const HISTORY_MONTHS: u32 = 6;
+const RECENT_DAYS: i64 = 30;
+/* A category is flagged when this month beats its average by this factor and margin */
+const SPIKE_FACTOR: f64 = 1.5;
+const SPIKE_MARGIN: f64 = 50.0;
+/* A transaction is flagged when it is this many deviations above the mean spend */
+const LARGE_DEVIATIONS: f64 = 3.0;
+const MIN_SAMPLES: usize = 10;
+const DUPLICATE_DAYS: i64 = 3;
+
+pub async fn anomalies(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Anomaly>>, ApiError> {
+    let pool = app_state.pool.clone();
+    let today = Utc::now().date_naive();
+    let from = history_start(today);
+
+    let history = sqlx::query_as!(
+        Transaction,
+        r#"SELECT rowid as "id!", account as "account!", date as "date!", description as "description!",
+        amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!",
+        version as "version!"
+        FROM finances WHERE l1_tag NOT IN ("Transfers", "Balance", "Repayments")
+        AND deleted_at IS NULL AND DATE(date) >= ?1 AND DATE(date) <= ?2
+        ORDER BY date"#,
+        from,
+        today
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let mut anomalies = category_spikes(&history, today);
+    anomalies.extend(large_transactions(&history, today));
+    anomalies.extend(possible_duplicates(&history, today));
+    Ok(Json(anomalies))
+}
+
+fn history_start(today: NaiveDate) -> NaiveDate {
+    let this_month = today.with_day(1).unwrap_or(today);
+    this_month
+        .checked_sub_months(Months::new(HISTORY_MONTHS))
+        .unwrap_or(this_month)
+}
+
+fn category_spikes(history: &[Transaction], today: NaiveDate) -> Vec<Anomaly> {
+    let this_month = (today.year(), today.month());
+    let from = history_start(today);
+    let from = (from.year(), from.month());
+    let mut monthly: HashMap<&str, HashMap<(i32, u32), f64>> = HashMap::new();
+    for t in history.iter().filter(|t| t.amount < 0.0) {
+        *monthly
+            .entry(&t.l1_tag)
+            .or_default()
+            .entry((t.date.year(), t.date.month()))
+            .or_default() -= t.amount;
+    }
+
+    let mut anomalies = vec![];
+    for (category, months) in monthly {
+        let Some(current) = months.get(&this_month) else {
+            continue;
+        };
+        /* Months without spend count towards the average too */
+        let previous: f64 = months
+            .iter()
+            .filter(|(month, _)| (from..this_month).contains(*month))
+            .map(|(_, spend)| *spend)
+            .sum();
+        if previous <= 0.0 {
+            continue;
+        }
+        let average = previous / f64::from(HISTORY_MONTHS);
+        if *current > average * SPIKE_FACTOR && current - average > SPIKE_MARGIN {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::CategorySpike,
+                message: format!(
+                    "{category} spend is £{current:.2} this month against a £{average:.2} average."
+                ),
+                amount: *current,
+                transaction_ids: vec![],
+            });
+        }
+    }
+    anomalies.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    anomalies
+}
+
+fn large_transactions(history: &[Transaction], today: NaiveDate) -> Vec<Anomaly> {
+    let spend: Vec<f64> = history
+        .iter()
+        .filter(|t| t.amount < 0.0)
+        .map(|t| -t.amount)
+        .collect();
+    if spend.len() < MIN_SAMPLES {
+        return vec![];
+    }
+    let mean = spend.iter().sum::<f64>() / spend.len() as f64;
+    let variance = spend.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / spend.len() as f64;
+    let threshold = variance.sqrt().mul_add(LARGE_DEVIATIONS, mean);
+
+    let recent = today - Duration::days(RECENT_DAYS);
+    history
+        .iter()
+        .filter(|t| t.date.date() > recent && -t.amount > threshold)
+        .map(|t| Anomaly {
+            kind: AnomalyKind::LargeTransaction,
+            message: format!(
+                "£{:.2} at {:?} on {} is much larger than usual.",
+                -t.amount,
+                t.description,
+                t.date.date()
+            ),
+            amount: -t.amount,
+            transaction_ids: vec![t.id],
+        })
+        .collect()
+}
+
+fn possible_duplicates(history: &[Transaction], today: NaiveDate) -> Vec<Anomaly> {
+    let recent = today - Duration::days(RECENT_DAYS);
+    let mut charges: Vec<&Transaction> = history
+        .iter()
+        .filter(|t| t.amount < 0.0 && t.date.date() > recent)
+        .collect();
+    charges.sort_by(|a, b| {
+        (&a.account, &a.description)
+            .cmp(&(&b.account, &b.description))
+            .then(a.amount.total_cmp(&b.amount))
+            .then(a.date.cmp(&b.date))
+    });
+
+    charges
+        .windows(2)
+        .filter(|pair| {
+            let (a, b) = (pair[0], pair[1]);
+            a.account == b.account
+                && a.description == b.description
+                && a.amount == b.amount
+                && (b.date - a.date) <= Duration::days(DUPLICATE_DAYS)
+        })
+        .map(|pair| Anomaly {
+            kind: AnomalyKind::PossibleDuplicate,
+            message: format!(
+                "{:?} charged £{:.2} twice on {} and {}.",
+                pair[0].description,
+                -pair[0].amount,
+                pair[0].date.date(),
+                pair[1].date.date()
+            ),
+            amount: -pair[0].amount,
+            transaction_ids: vec![pair[0].id, pair[1].id],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn spend(id: i64, date_str: &str, description: &str, amount: f64) -> Transaction {
+        Transaction {
+            id,
+            account: "Current".to_owned(),
+            date: date(date_str).and_hms_opt(12, 0, 0).unwrap(),
+            description: description.to_owned(),
+            amount: -amount,
+            l1_tag: "Food".to_owned(),
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn history_starts_on_a_month_boundary() {
+        assert_eq!(history_start(date("2023-07-19")), date("2023-01-01"));
+        assert_eq!(history_start(date("2023-03-01")), date("2022-09-01"));
+    }
+
+    #[test]
+    fn spikes_average_over_every_previous_month() {
+        let today = date("2023-07-19");
+        /* 600 in a single month averages 100 over six, not 600 */
+        let history = [
+            spend(1, "2023-02-10", "Shop", 600.0),
+            spend(2, "2023-07-02", "Shop", 200.0),
+        ];
+        let spikes = category_spikes(&history, today);
+        assert_eq!(spikes.len(), 1);
+        assert_eq!(spikes[0].amount, 200.0);
+
+        /* Spend before the window is ignored */
+        let history = [
+            spend(1, "2022-12-31", "Shop", 600.0),
+            spend(2, "2023-07-02", "Shop", 200.0),
+        ];
+        assert!(category_spikes(&history, today).is_empty());
+    }
+
+    #[test]
+    fn steady_spend_is_not_a_spike() {
+        let history: Vec<Transaction> = (1..=7)
+            .map(|month| spend(month, &format!("2023-{month:02}-05"), "Shop", 100.0))
+            .collect();
+        assert!(category_spikes(&history, date("2023-07-19")).is_empty());
+    }
+
+    #[test]
+    fn large_transactions_beat_the_threshold() {
+        let today = date("2023-07-19");
+        let mut history: Vec<Transaction> = (1..=40)
+            .map(|i| spend(i, &format!("2023-05-{:02}", i % 28 + 1), "Shop", 10.0))
+            .collect();
+        history.push(spend(41, "2023-07-10", "Sofa", 500.0));
+        history.push(spend(42, "2023-07-12", "Dinner", 60.0));
+        /* Old large spend adds to the baseline but isn't reported */
+        history.push(spend(43, "2023-05-01", "Flights", 500.0));
+
+        let large = large_transactions(&history, today);
+        assert_eq!(large.len(), 1);
+        assert_eq!(large[0].transaction_ids, [41]);
+
+        /* Too few samples to judge */
+        assert!(large_transactions(&history[35..], today).is_empty());
+    }
+
+    #[test]
+    fn duplicates_must_fall_within_the_window() {
+        let today = date("2023-07-19");
+        let history = [
+            spend(1, "2023-07-01", "Gym", 30.0),
+            spend(2, "2023-07-04", "Gym", 30.0),
+            spend(3, "2023-07-10", "Cinema", 12.0),
+            spend(4, "2023-07-14", "Cinema", 12.0),
+            spend(5, "2023-07-15", "Cafe", 3.0),
+            spend(6, "2023-07-15", "Cafe", 3.5),
+        ];
+        let duplicates = possible_duplicates(&history, today);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].transaction_ids, [1, 2]);
+    }
+}
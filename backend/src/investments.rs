@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode};
+use chrono::NaiveDate;
+use common::{
+    validate_prices, ApiError, Holding, HoldingValue, PricePoint, Trade, TradeKind, Transaction,
+};
+
+use crate::{extract::Json, AppState};
+
+pub async fn list_holdings(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HoldingValue>>, ApiError> {
+    let pool = app_state.pool.clone();
+    let rows = sqlx::query!(
+        r#"SELECT h.account, h.ticker, h.quantity, h.cost_basis,
+        p.date as "price_date?: NaiveDate", p.price as "price?: f64"
+        FROM holdings h LEFT JOIN prices p ON p.ticker = h.ticker
+        AND p.date = (SELECT MAX(date) FROM prices WHERE ticker = h.ticker)
+        WHERE h.quantity > 0 ORDER BY h.account, h.ticker"#
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let holdings = rows
+        .into_iter()
+        .map(|row| {
+            let holding = Holding {
+                account: row.account,
+                ticker: row.ticker,
+                quantity: row.quantity,
+                cost_basis: row.cost_basis,
+            };
+            HoldingValue::new(holding, row.price_date.zip(row.price))
+        })
+        .collect();
+
+    Ok(Json(holdings))
+}
+
+pub async fn record_trade(
+    State(app_state): State<Arc<AppState>>,
+    Json(trade): Json<Trade>,
+) -> Result<(StatusCode, Json<i64>), ApiError> {
+    app_state.config_db.lock().await.validate_trade(&trade)?;
+
+    let Trade {
+        kind,
+        ticker,
+        quantity,
+        transaction,
+    } = trade;
+    let quantity = match kind {
+        TradeKind::Dividend => 0.0,
+        _ => quantity,
+    };
+
+    let mut tx = app_state.pool.begin().await.map_err(ApiError::internal)?;
+    let mut holding = sqlx::query_as!(
+        Holding,
+        "SELECT account, ticker, quantity, cost_basis FROM holdings WHERE account = ?1 AND ticker = ?2",
+        transaction.account,
+        ticker
+    )
+    .fetch_optional(&mut tx)
+    .await
+    .map_err(ApiError::internal)?
+    .unwrap_or_else(|| Holding {
+        account: transaction.account.clone(),
+        ticker: ticker.clone(),
+        ..Holding::default()
+    });
+    holding
+        .apply(kind, quantity, transaction.amount)
+        .map_err(ApiError::bad_request)?;
+
+    let Transaction {
+        account,
+        date,
+        description,
+        amount,
+        l1_tag,
+        l2_tag,
+        l3_tag,
+        ..
+    } = transaction;
+    let finance_id = sqlx::query!(
+        r#"
+        INSERT INTO finances ( account, date, description, amount, l1_tag, l2_tag, l3_tag)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+        account,
+        date,
+        description,
+        amount,
+        l1_tag,
+        l2_tag,
+        l3_tag
+    )
+    .execute(&mut tx)
+    .await
+    .map_err(ApiError::internal)?
+    .last_insert_rowid();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO holdings ( account, ticker, quantity, cost_basis ) VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT (account, ticker) DO UPDATE
+        SET quantity = excluded.quantity, cost_basis = excluded.cost_basis
+        "#,
+        holding.account,
+        holding.ticker,
+        holding.quantity,
+        holding.cost_basis
+    )
+    .execute(&mut tx)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let kind = kind.as_str();
+    sqlx::query!(
+        r#"
+        INSERT INTO trades ( finance_id, holding_id, kind, quantity )
+        SELECT ?1, id, ?2, ?3 FROM holdings WHERE account = ?4 AND ticker = ?5
+        "#,
+        finance_id,
+        kind,
+        quantity,
+        holding.account,
+        holding.ticker
+    )
+    .execute(&mut tx)
+    .await
+    .map_err(ApiError::internal)?;
+    tx.commit().await.map_err(ApiError::internal)?;
+
+    Ok((StatusCode::CREATED, Json(finance_id)))
+}
+
+/* Expects a header row of date,ticker,price */
+pub async fn import_prices(
+    State(app_state): State<Arc<AppState>>,
+    body: String,
+) -> Result<Json<usize>, ApiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+    let prices = reader
+        .deserialize::<PricePoint>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ApiError::bad_request(format!("Bad price file: {e}.")))?;
+    validate_prices(&prices)?;
+
+    let mut tx = app_state.pool.begin().await.map_err(ApiError::internal)?;
+    for PricePoint {
+        date,
+        ticker,
+        price,
+    } in &prices
+    {
+        sqlx::query!(
+            r#"
+            INSERT INTO prices ( ticker, date, price ) VALUES (?1, ?2, ?3)
+            ON CONFLICT (ticker, date) DO UPDATE SET price = excluded.price
+            "#,
+            ticker,
+            date,
+            price
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(ApiError::internal)?;
+    }
+    tx.commit().await.map_err(ApiError::internal)?;
+
+    Ok(Json(prices.len()))
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use common::{read_journal, write_journal, ApiError, TransactionFilter};
+
+use crate::{
+    export::fetch_transactions,
+    extract::{Json, Query},
+    handlers::insert_transactions,
+    AppState,
+};
+
+pub async fn export_journal(
+    Query(filter): Query<TransactionFilter>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let liabilities = app_state
+        .config_db
+        .lock()
+        .await
+        .liability_accounts()
+        .to_vec();
+
+    let transactions = fetch_transactions(&app_state.pool, &filter).await?;
+    let journal = write_journal(&transactions, &liabilities)
+        .map_err(|e| ApiError::bad_request(format!("Can't write journal: {e}.")))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"transactions.journal\"",
+            ),
+        ],
+        journal,
+    ))
+}
+
+pub async fn import_journal(
+    State(app_state): State<Arc<AppState>>,
+    body: String,
+) -> Result<(StatusCode, Json<usize>), ApiError> {
+    let transactions =
+        read_journal(&body).map_err(|e| ApiError::bad_request(format!("Bad journal: {e}.")))?;
+    insert_transactions(&app_state, &transactions).await?;
+    Ok((StatusCode::CREATED, Json(transactions.len())))
+}
@@ -0,0 +1,76 @@
+#![warn(clippy::all, clippy::nursery)]
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Pool, Sqlite,
+};
+use tokio::sync::Mutex;
+
+pub mod backup;
+pub mod export;
+pub mod extract;
+pub mod frontend;
+pub mod goals;
+pub mod handlers;
+pub mod insights;
+pub mod investments;
+pub mod ledger;
+pub mod loans;
+pub mod qif;
+pub mod settings;
+pub mod tax;
+
+use backup::BackupSettings;
+use common::{Config, Transaction};
+use settings::Settings;
+
+pub type TransactionsDb = Arc<Mutex<Vec<Transaction>>>;
+
+pub type ConfigDb = Arc<Mutex<Config>>;
+
+pub fn load_config(path: &Path) -> anyhow::Result<ConfigDb> {
+    let config_file =
+        read_to_string(path).with_context(|| format!("reading config from {}", path.display()))?;
+    Ok(Arc::new(Mutex::new(serde_json::from_str(&config_file)?)))
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub config_db: ConfigDb,
+    pub pool: Pool<Sqlite>,
+    pub config_path: PathBuf,
+    pub backups: BackupSettings,
+}
+
+impl AppState {
+    /* Shared by the server and the finances command line tool */
+    pub async fn new(settings: &Settings) -> anyhow::Result<Self> {
+        if !settings.database.exists() {
+            tracing::warn!("creating a new database at {}", settings.database.display());
+        }
+        let options = SqliteConnectOptions::new()
+            .filename(&settings.database)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(settings.max_connections)
+            .connect_with(options)
+            .await?;
+
+        sqlx::migrate!().run(&pool).await?;
+
+        let config_db = load_config(&settings.config)?;
+
+        Ok(Self {
+            config_db,
+            pool,
+            config_path: settings.config.clone(),
+            backups: settings.backups.clone(),
+        })
+    }
+}
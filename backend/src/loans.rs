@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode};
+use chrono::{NaiveDate, Utc};
+use common::{ApiError, Loan, LoanOptions, LoanStatus, Transaction};
+
+use crate::{
+    extract::{Json, Query},
+    AppState,
+};
+
+pub async fn list_loans(
+    Query(opts): Query<LoanOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<LoanStatus>>, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let rows = sqlx::query!(
+        r#"SELECT id as "id!", name, principal, rate, term_months, start_date as "start_date!: NaiveDate",
+        payment_day, account FROM loans ORDER BY start_date, name"#
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let today = Utc::now().date_naive();
+    let mut loans = vec![];
+    for row in rows {
+        let loan = Loan {
+            id: row.id,
+            name: row.name,
+            principal: row.principal,
+            rate: row.rate,
+            term_months: u32::try_from(row.term_months).map_err(ApiError::internal)?,
+            start_date: row.start_date,
+            payment_day: u32::try_from(row.payment_day).map_err(ApiError::internal)?,
+            account: row.account,
+        };
+
+        /* Repayments:<loan name>, so loans paid from one account stay apart */
+        let repayments = sqlx::query_as!(
+            Transaction,
+            r#"SELECT rowid as "id!", account as "account!", date as "date!", description as "description!",
+            amount as "amount!", l1_tag as "l1_tag!", l2_tag as "l2_tag!", l3_tag as "l3_tag!",
+            version as "version!"
+            FROM finances WHERE l1_tag = "Repayments" AND deleted_at IS NULL
+            AND account = ?1 AND l2_tag = ?2 AND DATE(date) > ?3
+            ORDER BY date"#,
+            loan.account,
+            loan.name,
+            loan.start_date
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(ApiError::internal)?;
+
+        loans.push(LoanStatus::new(loan, &repayments, today, opts.extra));
+    }
+
+    Ok(Json(loans))
+}
+
+pub async fn create_loan(
+    State(app_state): State<Arc<AppState>>,
+    Json(loan): Json<Loan>,
+) -> Result<(StatusCode, Json<i64>), ApiError> {
+    app_state.config_db.lock().await.validate_loan(&loan)?;
+
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let taken = sqlx::query_scalar!("SELECT COUNT(*) FROM loans WHERE name = ?1", loan.name)
+        .fetch_one(&mut conn)
+        .await
+        .map_err(ApiError::internal)?;
+    if taken > 0 {
+        return Err(ApiError::conflict(format!(
+            "A loan named {:?} already exists.",
+            loan.name
+        )));
+    }
+
+    let id = sqlx::query!(
+        r#"
+        INSERT INTO loans ( name, principal, rate, term_months, start_date, payment_day, account )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+        loan.name,
+        loan.principal,
+        loan.rate,
+        loan.term_months,
+        loan.start_date,
+        loan.payment_day,
+        loan.account
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(ApiError::internal)?
+    .last_insert_rowid();
+
+    Ok((StatusCode::CREATED, Json(id)))
+}
+
+pub async fn delete_loan(
+    State(app_state): State<Arc<AppState>>,
+    Json(id): Json<i64>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = app_state.pool.acquire().await.map_err(ApiError::internal)?;
+    let result = sqlx::query!("DELETE FROM loans WHERE id = ?1", id)
+        .execute(&mut conn)
+        .await
+        .map_err(ApiError::internal)?;
+
+    match result.rows_affected() {
+        0 => Err(ApiError::not_found(format!("No loan {id}."))),
+        _ => Ok(StatusCode::OK),
+    }
+}
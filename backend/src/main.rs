@@ -1,42 +1,25 @@
 #![warn(clippy::all, clippy::nursery)]
-use std::{env, fs::read_to_string, net::SocketAddr, sync::Arc};
+use std::sync::Arc;
 
-use axum::{routing::get, Router};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
-use tokio::sync::Mutex;
-
-mod handlers;
-
-use common::{Config, Transaction};
-
-pub type TransactionsDb = Arc<Mutex<Vec<Transaction>>>;
-
-pub type ConfigDb = Arc<Mutex<Config>>;
-
-pub fn load_config() -> anyhow::Result<ConfigDb> {
-    let config_file = read_to_string("config.json")?;
-    Ok(Arc::new(Mutex::new(serde_json::from_str(&config_file)?)))
-}
-
-#[derive(Clone)]
-pub struct AppState {
-    pub config_db: ConfigDb,
-    pub pool: Pool<Sqlite>,
-}
+use axum::{
+    routing::{any, get, patch, post},
+    Router,
+};
+use backend::{
+    backup, export, frontend, goals, handlers, insights, investments, ledger, loans, qif,
+    settings::{Settings, SettingsArgs},
+    tax, AppState,
+};
+use clap::Parser;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
-    let db_url = env::var("DATABASE_URL")?;
-
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
-        .await?;
-
-    let config_db = load_config()?;
-
-    let state = Arc::new(AppState { config_db, pool });
+    let settings = Settings::load(SettingsArgs::parse())?;
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new(&settings.log_level)?)
+        .init();
+    let state = Arc::new(AppState::new(&settings).await?);
 
     let app = Router::new()
         .route("/api/", get(root))
@@ -47,16 +30,60 @@ async fn main() -> anyhow::Result<()> {
                 .patch(handlers::update_transaction)
                 .delete(handlers::delete_transaction),
         )
+        .route(
+            "/api/transactions/bulk",
+            patch(handlers::bulk_update_transactions),
+        )
+        .route(
+            "/api/trash",
+            get(handlers::list_trash)
+                .patch(handlers::restore_transaction)
+                .delete(handlers::purge_transaction),
+        )
         .route("/api/config/:key", get(handlers::get_config))
         .route("/api/accounts", get(handlers::get_account_totals))
         .route("/api/balance", get(handlers::balance_by_date))
         .route("/api/budget", get(handlers::budget_progress))
         .route("/api/category", get(handlers::category_spend))
+        .route("/api/compare", get(handlers::compare_category_spend))
+        .route("/api/flows", get(handlers::income_flows))
+        .route("/api/forecast", get(handlers::forecast_balance))
+        .route("/api/kpis", get(handlers::kpis))
+        .route(
+            "/api/goals",
+            get(goals::list_goals)
+                .post(goals::create_goal)
+                .delete(goals::delete_goal),
+        )
+        .route(
+            "/api/loans",
+            get(loans::list_loans)
+                .post(loans::create_loan)
+                .delete(loans::delete_loan),
+        )
+        .route("/api/investments/holdings", get(investments::list_holdings))
+        .route("/api/investments/trades", post(investments::record_trade))
+        .route("/api/investments/prices", post(investments::import_prices))
+        .route("/api/export", get(export::export_transactions))
+        .route(
+            "/api/ledger",
+            get(ledger::export_journal).post(ledger::import_journal),
+        )
+        .route("/api/qif", get(qif::export_qif).post(qif::import_qif))
+        .route("/api/tax", get(tax::tax_year_report))
+        .route("/api/tax/export", get(tax::export_tax_year_report))
+        .route(
+            "/api/backup",
+            get(backup::list_backups).post(backup::create_backup),
+        )
+        .route("/api/backup/restore", post(backup::restore_backup))
+        .route("/api/insights/anomalies", get(insights::anomalies))
+        .route("/api/*path", any(frontend::api_not_found))
         .with_state(state);
+    let app = frontend::serve(app, settings.static_dir.as_deref());
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8081));
-    tracing::info!("listening on {}", addr);
-    axum::Server::bind(&addr)
+    tracing::info!("listening on {}", settings.addr);
+    axum::Server::bind(&settings.addr)
         .serve(app.into_make_service())
         .await?;
     Ok(())
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use common::{read_qif, write_qif, ApiError, QifOptions, TransactionFilter};
+
+use crate::{
+    export::fetch_transactions,
+    extract::{Json, Query},
+    handlers::insert_transactions,
+    AppState,
+};
+
+pub async fn export_qif(
+    Query(filter): Query<TransactionFilter>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let transactions = fetch_transactions(&app_state.pool, &filter).await?;
+    let config = app_state.config_db.lock().await;
+    let qif = write_qif(&transactions, |account| config.qif_account_type(account));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/qif"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"transactions.qif\"",
+            ),
+        ],
+        qif,
+    ))
+}
+
+pub async fn import_qif(
+    Query(opts): Query<QifOptions>,
+    State(app_state): State<Arc<AppState>>,
+    body: String,
+) -> Result<(StatusCode, Json<usize>), ApiError> {
+    let transactions = read_qif(&body, opts.account.as_deref())
+        .map_err(|e| ApiError::bad_request(format!("Bad QIF file: {e}.")))?;
+    insert_transactions(&app_state, &transactions).await?;
+    Ok((StatusCode::CREATED, Json(transactions.len())))
+}
@@ -0,0 +1,299 @@
+use std::{
+    env,
+    fs::read_to_string,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::{Args, Parser};
+use serde::Deserialize;
+
+use crate::backup::BackupSettings;
+
+const DEFAULT_SETTINGS_FILE: &str = "finances.toml";
+const DEFAULT_BIND_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const DEFAULT_PORT: u16 = 8081;
+const DEFAULT_DATABASE: &str = "finances.sqlite";
+/* Where deployments from before these settings point at their database */
+const LEGACY_DATABASE_URL: &str = "DATABASE_URL";
+const DEFAULT_CONFIG: &str = "config.json";
+/* sqlx logs every query at info */
+const DEFAULT_LOG_LEVEL: &str = "info,sqlx::query=warn";
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+const DEFAULT_BACKUP_DIR: &str = "backups";
+const DEFAULT_BACKUP_KEEP: usize = 7;
+
+/* Where to find the database and config, shared with the finances CLI */
+#[derive(Debug, Default, Args)]
+pub struct DatabaseArgs {
+    #[arg(
+        long,
+        env = "FINANCES_SETTINGS",
+        help = "TOML settings file [default: finances.toml if present]"
+    )]
+    pub settings: Option<PathBuf>,
+    #[arg(
+        long,
+        env = "FINANCES_DATABASE",
+        help = "SQLite file, or a sqlite: URL in DATABASE_URL [default: finances.sqlite]"
+    )]
+    pub database: Option<PathBuf>,
+    #[arg(long, env = "FINANCES_CONFIG", help = "[default: config.json]")]
+    pub config: Option<PathBuf>,
+}
+
+impl DatabaseArgs {
+    fn read_file(&self) -> anyhow::Result<SettingsFile> {
+        let path = match &self.settings {
+            Some(path) => path.as_path(),
+            None if Path::new(DEFAULT_SETTINGS_FILE).exists() => Path::new(DEFAULT_SETTINGS_FILE),
+            None => return Ok(SettingsFile::default()),
+        };
+        let file = read_to_string(path)
+            .with_context(|| format!("reading settings from {}", path.display()))?;
+        toml::from_str(&file).with_context(|| format!("parsing settings in {}", path.display()))
+    }
+}
+
+/* Flags win over environment variables, which win over the TOML file.
+ * DATABASE_URL is still read, after FINANCES_DATABASE and before the file */
+#[derive(Debug, Default, Parser)]
+pub struct SettingsArgs {
+    #[command(flatten)]
+    pub database: DatabaseArgs,
+    #[arg(long, env = "FINANCES_BIND_ADDRESS", help = "[default: 127.0.0.1]")]
+    pub bind_address: Option<IpAddr>,
+    #[arg(long, env = "FINANCES_PORT", help = "[default: 8081]")]
+    pub port: Option<u16>,
+    #[arg(
+        long,
+        env = "FINANCES_LOG_LEVEL",
+        help = "Level or tracing filter [default: info,sqlx::query=warn]"
+    )]
+    pub log_level: Option<String>,
+    #[arg(long, env = "FINANCES_MAX_CONNECTIONS", help = "[default: 5]")]
+    pub max_connections: Option<u32>,
+    #[arg(long, env = "FINANCES_BACKUP_DIR", help = "[default: backups]")]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        env = "FINANCES_BACKUP_KEEP",
+        help = "Newest backups to keep [default: 7]"
+    )]
+    pub backup_keep: Option<usize>,
+    #[arg(
+        long,
+        env = "FINANCES_STATIC_DIR",
+        help = "Built frontend to serve [default: embedded assets, or frontend/dist]"
+    )]
+    pub static_dir: Option<PathBuf>,
+}
+
+/* The rest come from the file or the defaults */
+impl From<DatabaseArgs> for SettingsArgs {
+    fn from(database: DatabaseArgs) -> Self {
+        Self {
+            database,
+            ..Self::default()
+        }
+    }
+}
+
+/* Uses the same names as the flags, with underscores */
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsFile {
+    bind_address: Option<IpAddr>,
+    port: Option<u16>,
+    database: Option<PathBuf>,
+    config: Option<PathBuf>,
+    log_level: Option<String>,
+    max_connections: Option<u32>,
+    backup_dir: Option<PathBuf>,
+    backup_keep: Option<usize>,
+    static_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub addr: SocketAddr,
+    pub database: PathBuf,
+    pub config: PathBuf,
+    pub log_level: String,
+    pub max_connections: u32,
+    pub backups: BackupSettings,
+    pub static_dir: Option<PathBuf>,
+}
+
+impl Settings {
+    pub fn load(args: SettingsArgs) -> anyhow::Result<Self> {
+        let file = args.database.read_file()?;
+        let database_url = match args.database.database {
+            Some(_) => None,
+            None => env::var(LEGACY_DATABASE_URL).ok(),
+        };
+        Self::resolve(args, file, database_url)
+    }
+
+    fn resolve(
+        args: SettingsArgs,
+        file: SettingsFile,
+        database_url: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let keep = args
+            .backup_keep
+            .or(file.backup_keep)
+            .unwrap_or(DEFAULT_BACKUP_KEEP);
+        if keep == 0 {
+            anyhow::bail!("backup_keep must be at least 1");
+        }
+
+        let database = match (args.database.database, database_url, file.database) {
+            (Some(database), _, _) => database,
+            (None, Some(url), _) => parse_database_url(&url)?,
+            (None, None, Some(database)) => database,
+            (None, None, None) => DEFAULT_DATABASE.into(),
+        };
+
+        Ok(Self {
+            addr: SocketAddr::new(
+                args.bind_address
+                    .or(file.bind_address)
+                    .unwrap_or(DEFAULT_BIND_ADDRESS),
+                args.port.or(file.port).unwrap_or(DEFAULT_PORT),
+            ),
+            database,
+            config: args
+                .database
+                .config
+                .or(file.config)
+                .unwrap_or_else(|| DEFAULT_CONFIG.into()),
+            log_level: args
+                .log_level
+                .or(file.log_level)
+                .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_owned()),
+            max_connections: args
+                .max_connections
+                .or(file.max_connections)
+                .unwrap_or(DEFAULT_MAX_CONNECTIONS),
+            backups: BackupSettings {
+                dir: args
+                    .backup_dir
+                    .or(file.backup_dir)
+                    .unwrap_or_else(|| DEFAULT_BACKUP_DIR.into()),
+                keep,
+            },
+            static_dir: args.static_dir.or(file.static_dir),
+        })
+    }
+}
+
+/* sqlite://path or sqlite:path, as sqlx takes them. Options after ? are
+ * dropped, the database is always opened read-write and created if missing */
+fn parse_database_url(url: &str) -> anyhow::Result<PathBuf> {
+    let Some(rest) = url.strip_prefix("sqlite:") else {
+        anyhow::bail!("{LEGACY_DATABASE_URL} must be a sqlite: URL, got {url:?}");
+    };
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let path = rest.split_once('?').map_or(rest, |(path, _)| path);
+    Ok(path.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(flags: &[&str]) -> SettingsArgs {
+        SettingsArgs::try_parse_from(std::iter::once(&"backend").chain(flags)).unwrap()
+    }
+
+    fn file(toml: &str) -> SettingsFile {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn url(url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    #[test]
+    fn flag_beats_environment() {
+        let settings = Settings::resolve(
+            flags(&["--database", "flag.sqlite", "--port", "9000"]),
+            file("port = 9001"),
+            url("sqlite://env.sqlite?mode=rwc"),
+        )
+        .unwrap();
+        assert_eq!(settings.database, Path::new("flag.sqlite"));
+        assert_eq!(settings.addr.port(), 9000);
+
+        let settings = Settings::resolve(
+            flags(&["--database", "flag.sqlite"]),
+            file(""),
+            url("postgres://db"),
+        )
+        .unwrap();
+        assert_eq!(settings.database, Path::new("flag.sqlite"));
+    }
+
+    #[test]
+    fn environment_beats_file() {
+        let settings = Settings::resolve(
+            flags(&[]),
+            file("database = \"toml.sqlite\""),
+            url("sqlite:///srv/env.sqlite"),
+        )
+        .unwrap();
+        assert_eq!(settings.database, Path::new("/srv/env.sqlite"));
+
+        let settings = Settings::resolve(
+            flags(&[]),
+            file(""),
+            url("sqlite:env.sqlite?cache=shared&mode=rwc"),
+        )
+        .unwrap();
+        assert_eq!(settings.database, Path::new("env.sqlite"));
+
+        assert!(Settings::resolve(flags(&[]), file(""), url("postgres://db")).is_err());
+    }
+
+    #[test]
+    fn file_beats_defaults() {
+        let settings = Settings::resolve(
+            flags(&[]),
+            file("database = \"toml.sqlite\"\nport = 9001\nbackup_keep = 3"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(settings.database, Path::new("toml.sqlite"));
+        assert_eq!(settings.addr.port(), 9001);
+        assert_eq!(settings.backups.keep, 3);
+    }
+
+    #[test]
+    fn cli_uses_file_for_server_settings() {
+        let args = DatabaseArgs {
+            config: Some("cli.json".into()),
+            ..DatabaseArgs::default()
+        };
+        let settings = Settings::resolve(
+            args.into(),
+            file("config = \"toml.json\"\nbackup_dir = \"toml-backups\""),
+            None,
+        )
+        .unwrap();
+        assert_eq!(settings.config, Path::new("cli.json"));
+        assert_eq!(settings.backups.dir, Path::new("toml-backups"));
+    }
+
+    #[test]
+    fn defaults() {
+        let settings = Settings::resolve(flags(&[]), file(""), None).unwrap();
+        assert_eq!(settings.database, Path::new(DEFAULT_DATABASE));
+        assert_eq!(settings.addr.port(), DEFAULT_PORT);
+        assert_eq!(settings.log_level, DEFAULT_LOG_LEVEL);
+        assert_eq!(settings.backups.keep, DEFAULT_BACKUP_KEEP);
+
+        assert!(Settings::resolve(flags(&[]), file("backup_keep = 0"), None).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use common::{ApiError, TaxConfig, TaxLine, TaxYear, TaxYearOptions, TaxYearReport};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+
+use crate::{
+    extract::{Json, Query},
+    handlers::PERIOD_EXCLUSIONS,
+    AppState,
+};
+
+pub async fn tax_year_report(
+    Query(opts): Query<TaxYearOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<TaxYearReport>, ApiError> {
+    opts.validate()?;
+    let tax = app_state.config_db.lock().await.tax().clone();
+    let report = build_report(&app_state.pool, &tax, TaxYear(opts.year)).await?;
+    Ok(Json(report))
+}
+
+pub async fn export_tax_year_report(
+    Query(opts): Query<TaxYearOptions>,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    opts.validate()?;
+    let tax = app_state.config_db.lock().await.tax().clone();
+    let report = build_report(&app_state.pool, &tax, TaxYear(opts.year)).await?;
+
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record(["section", "name", "amount"])
+        .map_err(ApiError::internal)?;
+    for (section, name, amount) in report.rows() {
+        writer
+            .write_record([section, &name, &format!("{amount:.2}")])
+            .map_err(ApiError::internal)?;
+    }
+    let csv = writer.into_inner().map_err(ApiError::internal)?;
+    let csv = String::from_utf8(csv).map_err(ApiError::internal)?;
+
+    let filename = format!("attachment; filename=\"tax-year-{}.csv\"", opts.year);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_owned()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        csv,
+    ))
+}
+
+async fn build_report(
+    pool: &SqlitePool,
+    tax: &TaxConfig,
+    year: TaxYear,
+) -> Result<TaxYearReport, ApiError> {
+    /* Interest and dividends are reported separately from other income */
+    let mut query_builder = in_year(year, "l1_tag, l2_tag, TOTAL(amount) as amount");
+    query_builder.push(format!(" AND {PERIOD_EXCLUSIONS} AND NOT "));
+    push_tags(&mut query_builder, &tax.interest_tags);
+    query_builder
+        .push(" AND rowid NOT IN (SELECT finance_id FROM trades WHERE kind = 'Dividend')")
+        .push(" GROUP BY l1_tag, l2_tag HAVING TOTAL(amount) > 0 ORDER BY l1_tag, l2_tag");
+    let income = tax_lines(pool, query_builder).await?;
+
+    let mut query_builder = in_year(year, "TOTAL(amount) as amount");
+    query_builder.push(" AND ");
+    push_tags(&mut query_builder, &tax.interest_tags);
+    let interest = total(pool, query_builder).await?;
+
+    let mut query_builder = in_year(year, "TOTAL(amount) as amount");
+    query_builder.push(" AND rowid IN (SELECT finance_id FROM trades WHERE kind = 'Dividend')");
+    let dividends = total(pool, query_builder).await?;
+
+    let mut query_builder = in_year(year, "ABS(TOTAL(amount)) as amount");
+    query_builder.push(" AND ");
+    push_tags(&mut query_builder, &tax.pension_tags);
+    let pension_contributions = total(pool, query_builder).await?;
+
+    let mut query_builder = in_year(year, "l1_tag, l2_tag, ABS(TOTAL(amount)) as amount");
+    query_builder.push(" AND ");
+    push_tags(&mut query_builder, &tax.deductible_tags);
+    query_builder.push(" GROUP BY l1_tag, l2_tag ORDER BY l1_tag, l2_tag");
+    let deductible = tax_lines(pool, query_builder).await?;
+
+    Ok(TaxYearReport {
+        year,
+        income,
+        interest,
+        dividends,
+        pension_contributions,
+        deductible,
+    })
+}
+
+fn in_year(year: TaxYear, columns: &str) -> QueryBuilder<'static, Sqlite> {
+    let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "SELECT {columns} FROM finances WHERE deleted_at IS NULL"
+    ));
+    query_builder
+        .push(" AND DATE(date) >= ")
+        .push_bind(year.start())
+        .push(" AND DATE(date) < ")
+        .push_bind(year.end());
+    query_builder
+}
+
+/* Matches a tag at any level, an empty list matches nothing */
+fn push_tags(query_builder: &mut QueryBuilder<Sqlite>, tags: &[String]) {
+    if tags.is_empty() {
+        query_builder.push("0");
+        return;
+    }
+    query_builder.push("(");
+    for (i, column) in ["l1_tag", "l2_tag", "l3_tag"].iter().enumerate() {
+        if i > 0 {
+            query_builder.push(" OR ");
+        }
+        query_builder.push(format!("{column} IN ("));
+        let mut separated = query_builder.separated(", ");
+        for tag in tags {
+            separated.push_bind(tag.clone());
+        }
+        separated.push_unseparated(")");
+    }
+    query_builder.push(")");
+}
+
+async fn tax_lines(
+    pool: &SqlitePool,
+    mut query_builder: QueryBuilder<'_, Sqlite>,
+) -> Result<Vec<TaxLine>, ApiError> {
+    query_builder
+        .build()
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            let l1_tag: String = row.try_get("l1_tag")?;
+            let l2_tag: String = row.try_get("l2_tag")?;
+            let name = match l2_tag.as_str() {
+                "" => l1_tag,
+                _ => format!("{l1_tag} / {l2_tag}"),
+            };
+            Ok(TaxLine {
+                name,
+                amount: row.try_get("amount")?,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(ApiError::internal)
+}
+
+async fn total(
+    pool: &SqlitePool,
+    mut query_builder: QueryBuilder<'_, Sqlite>,
+) -> Result<f64, ApiError> {
+    query_builder
+        .build()
+        .try_map(|row: sqlx::sqlite::SqliteRow| row.try_get("amount"))
+        .fetch_one(pool)
+        .await
+        .map_err(ApiError::internal)
+}
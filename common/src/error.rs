@@ -0,0 +1,145 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    BadRequest,
+    NotFound,
+    Conflict,
+    Validation,
+    Internal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default)]
+    pub field_errors: Vec<FieldError>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            field_errors: vec![],
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BadRequest, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn validation(field_errors: Vec<FieldError>) -> Self {
+        Self {
+            field_errors,
+            ..Self::new(ErrorCode::Validation, "Validation failed.")
+        }
+    }
+
+    pub fn field_error(&self, field: &str) -> Option<&str> {
+        self.field_errors
+            .iter()
+            .find(|e| e.field == field)
+            .map(|e| e.message.as_str())
+    }
+
+    pub fn internal(error: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::Internal, error.to_string())
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)?;
+        for field_error in &self.field_errors {
+            write!(f, " ({}: {})", field_error.field, field_error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[cfg(feature = "axum")]
+mod response {
+    use axum::{
+        extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+        http::StatusCode,
+        response::{IntoResponse, Response},
+        Json,
+    };
+
+    use super::{ApiError, ErrorCode};
+
+    impl ErrorCode {
+        pub const fn status(self) -> StatusCode {
+            match self {
+                Self::BadRequest => StatusCode::BAD_REQUEST,
+                Self::NotFound => StatusCode::NOT_FOUND,
+                Self::Conflict => StatusCode::CONFLICT,
+                Self::Validation => StatusCode::UNPROCESSABLE_ENTITY,
+                Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+    }
+
+    impl IntoResponse for ApiError {
+        fn into_response(self) -> Response {
+            /* The detail can hold SQL, constraint names or paths, so it only
+             * goes to the log */
+            let error = if self.code == ErrorCode::Internal {
+                tracing::error!("{self}");
+                Self::new(ErrorCode::Internal, "Internal error.")
+            } else {
+                self
+            };
+            (error.code.status(), Json(error)).into_response()
+        }
+    }
+
+    /* Bodies, query strings and paths axum can't parse get the same JSON
+     * error as everything else, the frontend only reads ApiError */
+    impl From<JsonRejection> for ApiError {
+        fn from(rejection: JsonRejection) -> Self {
+            Self::bad_request(rejection.body_text())
+        }
+    }
+
+    impl From<QueryRejection> for ApiError {
+        fn from(rejection: QueryRejection) -> Self {
+            Self::bad_request(rejection.body_text())
+        }
+    }
+
+    impl From<PathRejection> for ApiError {
+        fn from(rejection: PathRejection) -> Self {
+            Self::bad_request(rejection.body_text())
+        }
+    }
+}
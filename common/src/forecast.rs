@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{period::last_day_of_month, BalanceOverTime, Transaction};
+
+/* Seen in at least this many separate months to count as recurring */
+const MIN_RECURRING_MONTHS: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastOptions {
+    pub days: i64,
+}
+
+impl ForecastOptions {
+    pub fn url_encode(&self) -> String {
+        format!("days={}", self.days)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecurringItem {
+    pub description: String,
+    pub amount: f64,
+    pub day_of_month: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Forecast {
+    pub recurring: Vec<RecurringItem>,
+    pub daily_spend: f64,
+}
+
+impl Forecast {
+    pub fn from_history(transactions: &[Transaction], from: NaiveDate, to: NaiveDate) -> Self {
+        let mut by_description: HashMap<&str, Vec<&Transaction>> = HashMap::new();
+        for t in transactions {
+            by_description.entry(&t.description).or_default().push(t);
+        }
+
+        let mut recurring = vec![];
+        let mut discretionary = 0.0;
+        for (description, items) in by_description {
+            let months: HashSet<(i32, u32)> = items
+                .iter()
+                .map(|t| (t.date.year(), t.date.month()))
+                .collect();
+            if months.len() < MIN_RECURRING_MONTHS {
+                discretionary += items
+                    .iter()
+                    .map(|t| t.amount)
+                    .filter(|a| *a < 0.0)
+                    .sum::<f64>();
+                continue;
+            }
+
+            let mut days: Vec<u32> = items.iter().map(|t| t.date.day()).collect();
+            days.sort_unstable();
+            recurring.push(RecurringItem {
+                description: description.to_owned(),
+                amount: items.iter().map(|t| t.amount).sum::<f64>() / months.len() as f64,
+                day_of_month: days[days.len() / 2],
+            });
+        }
+        recurring.sort_by(|a, b| a.description.cmp(&b.description));
+
+        let window = (to - from).num_days().max(1);
+        Self {
+            recurring,
+            daily_spend: discretionary / window as f64,
+        }
+    }
+
+    pub fn project(&self, start: NaiveDate, balance: f64, days: i64) -> BalanceOverTime {
+        let mut dates = Vec::with_capacity(days.max(0) as usize);
+        let mut balances = Vec::with_capacity(days.max(0) as usize);
+
+        let mut balance = balance;
+        for offset in 1..=days {
+            let date = start + Duration::days(offset);
+            let last_day = last_day_of_month(date.year(), date.month()).day();
+
+            balance += self.daily_spend;
+            balance += self
+                .recurring
+                .iter()
+                .filter(|item| item.day_of_month.min(last_day) == date.day())
+                .map(|item| item.amount)
+                .sum::<f64>();
+
+            dates.push(date.format("%Y-%m-%d").to_string());
+            balances.push(balance);
+        }
+
+        BalanceOverTime { dates, balances }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn transaction(date_str: &str, description: &str, amount: f64) -> Transaction {
+        Transaction {
+            date: date(date_str).and_hms_opt(0, 0, 0).unwrap(),
+            description: description.to_owned(),
+            amount,
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn history_splits_recurring_from_discretionary() {
+        let history = [
+            transaction("2023-01-01", "Rent", -500.0),
+            transaction("2023-02-02", "Rent", -500.0),
+            transaction("2023-03-01", "Rent", -500.0),
+            transaction("2023-01-31", "Salary", 2000.0),
+            transaction("2023-02-28", "Salary", 2000.0),
+            transaction("2023-03-31", "Salary", 2000.0),
+            transaction("2023-01-10", "Coffee", -3.0),
+            transaction("2023-02-10", "Coffee", -3.0),
+            transaction("2023-02-11", "Refund", 20.0),
+        ];
+        let forecast = Forecast::from_history(&history, date("2023-01-01"), date("2023-03-31"));
+
+        assert_eq!(
+            forecast.recurring,
+            [
+                RecurringItem {
+                    description: "Rent".to_owned(),
+                    amount: -500.0,
+                    day_of_month: 1,
+                },
+                RecurringItem {
+                    description: "Salary".to_owned(),
+                    amount: 2000.0,
+                    day_of_month: 31,
+                },
+            ]
+        );
+        /* Only spending counts, the refund is ignored */
+        assert!((forecast.daily_spend - -6.0 / 89.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn projection_clamps_paydays_to_short_months() {
+        let forecast = Forecast {
+            recurring: vec![
+                RecurringItem {
+                    description: "Rent".to_owned(),
+                    amount: -500.0,
+                    day_of_month: 1,
+                },
+                RecurringItem {
+                    description: "Salary".to_owned(),
+                    amount: 2000.0,
+                    day_of_month: 31,
+                },
+            ],
+            daily_spend: -1.0,
+        };
+        let projection = forecast.project(date("2023-02-26"), 100.0, 4);
+        assert_eq!(
+            projection.dates,
+            ["2023-02-27", "2023-02-28", "2023-03-01", "2023-03-02"]
+        );
+        assert_eq!(projection.balances, [99.0, 2098.0, 1597.0, 1596.0]);
+    }
+
+    #[test]
+    fn empty_history_projects_a_flat_balance() {
+        let forecast = Forecast::from_history(&[], date("2023-01-01"), date("2023-01-01"));
+        assert_eq!(forecast.daily_spend, 0.0);
+        assert_eq!(
+            forecast.project(date("2023-01-01"), 50.0, 2).balances,
+            [50.0, 50.0]
+        );
+        assert!(forecast
+            .project(date("2023-01-01"), 50.0, 0)
+            .dates
+            .is_empty());
+    }
+}
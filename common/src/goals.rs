@@ -0,0 +1,102 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum GoalLink {
+    Account(String),
+    Tag(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Goal {
+    pub id: i64,
+    pub name: String,
+    pub target: f64,
+    pub target_date: NaiveDate,
+    pub link: GoalLink,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub saved: f64,
+    pub remaining: f64,
+    pub months_left: i64,
+    pub monthly_contribution: f64,
+}
+
+impl GoalProgress {
+    pub fn new(goal: Goal, saved: f64, today: NaiveDate) -> Self {
+        let remaining = (goal.target - saved).max(0.0);
+        let months_left = (goal.target_date.year() - today.year()) as i64 * 12
+            + goal.target_date.month() as i64
+            - today.month() as i64;
+        /* Anything still owed at or after the deadline is due now */
+        let monthly_contribution = remaining / months_left.max(1) as f64;
+
+        Self {
+            goal,
+            saved,
+            remaining,
+            months_left: months_left.max(0),
+            monthly_contribution,
+        }
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.goal.target <= 0.0 {
+            return 1.0;
+        }
+        (self.saved / self.goal.target).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn goal(target_date: &str) -> Goal {
+        Goal {
+            id: 1,
+            name: "Holiday".to_owned(),
+            target: 1200.0,
+            target_date: date(target_date),
+            link: GoalLink::Tag("Holiday".to_owned()),
+        }
+    }
+
+    #[test]
+    fn contribution_spreads_remainder_over_months_left() {
+        let progress = GoalProgress::new(goal("2024-01-15"), 600.0, date("2023-07-20"));
+        assert_eq!(progress.remaining, 600.0);
+        assert_eq!(progress.months_left, 6);
+        assert_eq!(progress.monthly_contribution, 100.0);
+        assert_eq!(progress.progress(), 0.5);
+    }
+
+    #[test]
+    fn met_goal_needs_nothing_more() {
+        let progress = GoalProgress::new(goal("2024-01-15"), 1500.0, date("2023-07-20"));
+        assert_eq!(progress.remaining, 0.0);
+        assert_eq!(progress.monthly_contribution, 0.0);
+        assert_eq!(progress.progress(), 1.0);
+    }
+
+    #[test]
+    fn deadline_this_month_is_due_now() {
+        let progress = GoalProgress::new(goal("2023-07-31"), 200.0, date("2023-07-01"));
+        assert_eq!(progress.months_left, 0);
+        assert_eq!(progress.monthly_contribution, 1000.0);
+    }
+
+    #[test]
+    fn past_deadline_is_due_now() {
+        let progress = GoalProgress::new(goal("2023-01-15"), 200.0, date("2023-07-20"));
+        assert_eq!(progress.months_left, 0);
+        assert_eq!(progress.monthly_contribution, 1000.0);
+    }
+}
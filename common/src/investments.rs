@@ -0,0 +1,155 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::Transaction;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TradeKind {
+    Buy,
+    Sell,
+    Dividend,
+}
+
+impl TradeKind {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Buy => "Buy",
+            Self::Sell => "Sell",
+            Self::Dividend => "Dividend",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Trade {
+    pub kind: TradeKind,
+    pub ticker: String,
+    pub quantity: f64,
+    pub transaction: Transaction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Holding {
+    pub account: String,
+    pub ticker: String,
+    pub quantity: f64,
+    pub cost_basis: f64,
+}
+
+impl Holding {
+    /* Buys add their cash cost, sells release cost at the average price paid */
+    pub fn apply(&mut self, kind: TradeKind, quantity: f64, amount: f64) -> Result<(), String> {
+        match kind {
+            TradeKind::Buy => {
+                self.quantity += quantity;
+                self.cost_basis += amount.abs();
+            }
+            TradeKind::Sell => {
+                if quantity > self.quantity {
+                    return Err(format!(
+                        "Cannot sell {quantity} {} when holding {}.",
+                        self.ticker, self.quantity
+                    ));
+                }
+                self.cost_basis -= self.cost_basis * quantity / self.quantity;
+                self.quantity -= quantity;
+            }
+            TradeKind::Dividend => (),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HoldingValue {
+    pub holding: Holding,
+    pub price: Option<f64>,
+    pub price_date: Option<NaiveDate>,
+    pub market_value: f64,
+    pub gain: f64,
+}
+
+impl HoldingValue {
+    /* Holdings without a price yet are carried at cost */
+    pub fn new(holding: Holding, price: Option<(NaiveDate, f64)>) -> Self {
+        let market_value = price.map_or(holding.cost_basis, |(_, p)| p * holding.quantity);
+        Self {
+            gain: market_value - holding.cost_basis,
+            price: price.map(|(_, p)| p),
+            price_date: price.map(|(d, _)| d),
+            market_value,
+            holding,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PricePoint {
+    pub date: NaiveDate,
+    pub ticker: String,
+    pub price: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(quantity: f64, cost_basis: f64) -> Holding {
+        Holding {
+            account: "ISA".to_owned(),
+            ticker: "VWRL".to_owned(),
+            quantity,
+            cost_basis,
+        }
+    }
+
+    #[test]
+    fn buys_add_cash_cost() {
+        let mut h = holding(0.0, 0.0);
+        h.apply(TradeKind::Buy, 10.0, -900.0).unwrap();
+        h.apply(TradeKind::Buy, 10.0, -1100.0).unwrap();
+        assert_eq!((h.quantity, h.cost_basis), (20.0, 2000.0));
+    }
+
+    #[test]
+    fn sells_release_average_cost() {
+        let mut h = holding(20.0, 2000.0);
+        h.apply(TradeKind::Sell, 5.0, 600.0).unwrap();
+        assert_eq!((h.quantity, h.cost_basis), (15.0, 1500.0));
+
+        h.apply(TradeKind::Sell, 15.0, 1700.0).unwrap();
+        assert_eq!((h.quantity, h.cost_basis), (0.0, 0.0));
+    }
+
+    #[test]
+    fn overselling_leaves_the_holding_alone() {
+        let mut h = holding(5.0, 500.0);
+        assert_eq!(
+            h.apply(TradeKind::Sell, 6.0, 700.0),
+            Err("Cannot sell 6 VWRL when holding 5.".to_owned())
+        );
+        assert_eq!(h, holding(5.0, 500.0));
+    }
+
+    #[test]
+    fn dividends_change_nothing() {
+        let mut h = holding(5.0, 500.0);
+        h.apply(TradeKind::Dividend, 0.0, 12.0).unwrap();
+        assert_eq!(h, holding(5.0, 500.0));
+    }
+
+    #[test]
+    fn value_uses_latest_price_or_cost() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+        let priced = HoldingValue::new(holding(10.0, 900.0), Some((date, 95.0)));
+        assert_eq!(priced.price, Some(95.0));
+        assert_eq!(priced.price_date, Some(date));
+        assert_eq!(priced.market_value, 950.0);
+        assert_eq!(priced.gain, 50.0);
+
+        let unpriced = HoldingValue::new(holding(10.0, 900.0), None);
+        assert_eq!(unpriced.market_value, 900.0);
+        assert_eq!(unpriced.gain, 0.0);
+        assert_eq!(unpriced.price, None);
+    }
+}
@@ -0,0 +1,412 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::Transaction;
+
+const ASSETS: &str = "assets";
+const LIABILITIES: &str = "liabilities";
+const EXPENSES: &str = "expenses";
+const INCOME: &str = "income";
+const COMMODITY: &str = "£";
+
+/* Each transaction becomes two postings, the account side and the tag side.
+ * Tags keep their position, so an empty l2 is written as Food::Snacks */
+pub fn write_journal(
+    transactions: &[Transaction],
+    liabilities: &[String],
+) -> Result<String, String> {
+    let mut journal = String::new();
+    for t in transactions {
+        check_writable(t)?;
+        let kind = if liabilities.contains(&t.account) {
+            LIABILITIES
+        } else {
+            ASSETS
+        };
+        let category = if t.amount < 0.0 { EXPENSES } else { INCOME };
+        let mut tags = vec![t.l1_tag.as_str(), t.l2_tag.as_str(), t.l3_tag.as_str()];
+        while tags.last() == Some(&"") {
+            tags.pop();
+        }
+
+        journal.push_str(&format!("{} {}\n", t.date.date(), t.description));
+        journal.push_str(&format!(
+            "    {category}:{}  {}\n",
+            tags.join(":"),
+            format_amount(-t.amount)
+        ));
+        journal.push_str(&format!(
+            "    {kind}:{}  {}\n\n",
+            t.account,
+            format_amount(t.amount)
+        ));
+    }
+    Ok(journal)
+}
+
+/* The format has no escapes. A ; starts a comment, : separates tags and two
+ * spaces or a tab end a posting's name */
+fn check_writable(t: &Transaction) -> Result<(), String> {
+    if t.description.contains(&[';', '\n'][..]) {
+        return Err(format!(
+            "transaction {} has a ; or line break in its description {:?}",
+            t.id, t.description
+        ));
+    }
+    let names = [
+        ("account", &t.account),
+        ("tag", &t.l1_tag),
+        ("tag", &t.l2_tag),
+        ("tag", &t.l3_tag),
+    ];
+    for (kind, name) in names {
+        if name.contains(&[':', ';', '\t', '\n'][..]) || name.contains("  ") {
+            return Err(format!(
+                "transaction {} has a :, ; or wide space in its {kind} {name:?}",
+                t.id
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn format_amount(amount: f64) -> String {
+    let sign = if amount < 0.0 { "-" } else { "" };
+    format!("{sign}{COMMODITY}{:.2}", amount.abs())
+}
+
+struct Entry {
+    line: usize,
+    date: NaiveDate,
+    description: String,
+    postings: Vec<(String, Option<f64>)>,
+}
+
+/* Reads the subset of the journal format that write_journal produces, plus
+ * status marks, comments, directives and one elided posting amount */
+pub fn read_journal(journal: &str) -> Result<Vec<Transaction>, String> {
+    let mut entries: Vec<Entry> = vec![];
+    for (i, raw) in journal.lines().enumerate() {
+        let line = i + 1;
+        let text = strip_comment(raw);
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        if raw.starts_with(char::is_whitespace) {
+            let Some(entry) = entries.last_mut() else {
+                return Err(format!("line {line}: posting outside a transaction"));
+            };
+            entry.postings.push(parse_posting(text.trim(), line)?);
+            continue;
+        }
+
+        let (date, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let Some(date) = parse_date(date) else {
+            /* Directives such as account, commodity or P lines */
+            continue;
+        };
+        let description = rest.trim_start();
+        let description = description
+            .strip_prefix(&['*', '!'][..])
+            .unwrap_or(description)
+            .trim();
+        entries.push(Entry {
+            line,
+            date,
+            description: description.to_owned(),
+            postings: vec![],
+        });
+    }
+
+    entries.into_iter().map(into_transaction).collect()
+}
+
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with(&[';', '#', '*'][..]) {
+        return "";
+    }
+    line.split(';').next().unwrap_or(line)
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%Y/%m/%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+/* Account names may hold single spaces, the amount follows two spaces or a tab */
+fn parse_posting(text: &str, line: usize) -> Result<(String, Option<f64>), String> {
+    let split = text.find("  ").into_iter().chain(text.find('\t')).min();
+    let Some(split) = split else {
+        return Ok((text.to_owned(), None));
+    };
+    let (account, amount) = text.split_at(split);
+    let amount = amount.trim();
+    let amount =
+        parse_amount(amount).ok_or_else(|| format!("line {line}: bad amount {amount:?}"))?;
+    Ok((account.trim().to_owned(), Some(amount)))
+}
+
+/* A single £ amount, with the sign either side of the £ and optional
+ * thousands separators. Prices, other commodities and anything else are
+ * refused rather than read as some other number */
+fn parse_amount(amount: &str) -> Option<f64> {
+    let negative = amount.starts_with('-');
+    let rest = amount.strip_prefix('-').unwrap_or(amount);
+    let rest = rest.strip_prefix(COMMODITY).unwrap_or(rest).trim_start();
+    let (negative, rest) = match rest.strip_prefix('-') {
+        Some(rest) if !negative => (true, rest),
+        _ => (negative, rest),
+    };
+
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    let (whole, fraction) = rest.split_once('.').unwrap_or((rest, ""));
+    let mut groups = whole.split(',');
+    let first = groups.next().unwrap_or_default();
+    let mut rest_of_whole = String::new();
+    for group in groups {
+        if group.len() != 3 || !digits(group) {
+            return None;
+        }
+        rest_of_whole.push_str(group);
+    }
+    if first.is_empty() || !digits(first) || !digits(fraction) {
+        return None;
+    }
+
+    let number: f64 = format!("{first}{rest_of_whole}.{fraction}").parse().ok()?;
+    Some(if negative { -number } else { number })
+}
+
+fn into_transaction(entry: Entry) -> Result<Transaction, String> {
+    let Entry {
+        line,
+        date,
+        description,
+        postings,
+    } = entry;
+    let [first, second] = <[_; 2]>::try_from(postings)
+        .map_err(|p| format!("line {line}: expected 2 postings, found {}", p.len()))?;
+
+    let is_account = |name: &str| {
+        [ASSETS, LIABILITIES]
+            .iter()
+            .any(|prefix| name.starts_with(&format!("{prefix}:")))
+    };
+    let (account, category) = match (is_account(&first.0), is_account(&second.0)) {
+        (true, false) => (first, second),
+        (false, true) => (second, first),
+        _ => {
+            return Err(format!(
+                "line {line}: expected one assets or liabilities posting"
+            ))
+        }
+    };
+
+    let amount = match (account.1, category.1) {
+        (Some(amount), _) => amount,
+        (None, Some(amount)) => -amount,
+        (None, None) => return Err(format!("line {line}: no amounts")),
+    };
+
+    let account_name = account
+        .0
+        .split_once(':')
+        .map_or_else(String::new, |(_, name)| name.to_owned());
+    let mut tags = category
+        .0
+        .split(':')
+        .skip(1)
+        .map(str::to_owned)
+        .chain(std::iter::repeat(String::new()));
+    let mut next_tag = || tags.next().unwrap_or_default();
+
+    Ok(Transaction {
+        id: 0,
+        account: account_name,
+        date: NaiveDateTime::new(date, NaiveTime::default()),
+        description,
+        amount,
+        l1_tag: next_tag(),
+        l2_tag: next_tag(),
+        l3_tag: next_tag(),
+        version: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(
+        account: &str,
+        date: &str,
+        description: &str,
+        amount: f64,
+        tags: [&str; 3],
+    ) -> Transaction {
+        Transaction {
+            id: 0,
+            account: account.to_owned(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            description: description.to_owned(),
+            amount,
+            l1_tag: tags[0].to_owned(),
+            l2_tag: tags[1].to_owned(),
+            l3_tag: tags[2].to_owned(),
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn journal_round_trips() {
+        let transactions = vec![
+            transaction(
+                "Current",
+                "2023-05-01",
+                "Fish & Chips",
+                -12.5,
+                ["Food", "Takeaway", "Chippy"],
+            ),
+            transaction(
+                "Current",
+                "2023-05-02",
+                "Pay day",
+                2000.0,
+                ["Income", "Salary", ""],
+            ),
+            transaction(
+                "Credit Card",
+                "2023-05-03",
+                "Refund",
+                1234.56,
+                ["Shopping", "", ""],
+            ),
+        ];
+        let liabilities = ["Credit Card".to_owned()];
+
+        let journal = write_journal(&transactions, &liabilities).unwrap();
+        assert!(journal.contains("    expenses:Food:Takeaway:Chippy  £12.50\n"));
+        assert!(journal.contains("    liabilities:Credit Card  £1234.56\n"));
+        assert_eq!(read_journal(&journal).unwrap(), transactions);
+
+        let again = write_journal(&read_journal(&journal).unwrap(), &liabilities).unwrap();
+        assert_eq!(again, journal);
+    }
+
+    #[test]
+    fn journal_keeps_tag_positions() {
+        let transactions = vec![
+            transaction(
+                "Current",
+                "2023-05-01",
+                "Crisps",
+                -1.5,
+                ["Food", "", "Snacks"],
+            ),
+            transaction("Current", "2023-05-02", "Gift", 20.0, ["", "", "Birthday"]),
+        ];
+        let journal = write_journal(&transactions, &[]).unwrap();
+        assert!(journal.contains("    expenses:Food::Snacks  £1.50\n"));
+        assert!(journal.contains("    income:::Birthday  -£20.00\n"));
+        assert_eq!(read_journal(&journal).unwrap(), transactions);
+    }
+
+    #[test]
+    fn journal_rejects_what_it_cannot_write() {
+        let comment = transaction(
+            "Current",
+            "2023-05-01",
+            "Lunch; team",
+            -9.0,
+            ["Food", "", ""],
+        );
+        assert_eq!(
+            write_journal(&[comment], &[]).unwrap_err(),
+            "transaction 0 has a ; or line break in its description \"Lunch; team\""
+        );
+
+        let nested = transaction("Current", "2023-05-01", "Lunch", -9.0, ["Food:Out", "", ""]);
+        assert_eq!(
+            write_journal(&[nested], &[]).unwrap_err(),
+            "transaction 0 has a :, ; or wide space in its tag \"Food:Out\""
+        );
+
+        for account in ["Savings:ISA", "Joint  Account", "Joint\tAccount"] {
+            let t = transaction(account, "2023-05-01", "Lunch", -9.0, ["Food", "", ""]);
+            assert_eq!(
+                write_journal(&[t], &[]).unwrap_err(),
+                format!("transaction 0 has a :, ; or wide space in its account {account:?}")
+            );
+        }
+    }
+
+    #[test]
+    fn journal_refuses_amounts_it_cannot_read() {
+        assert_eq!(parse_amount("£12.50"), Some(12.5));
+        assert_eq!(parse_amount("-£1,234.00"), Some(-1234.0));
+        assert_eq!(parse_amount("£-3"), Some(-3.0));
+        assert_eq!(parse_amount("7.25"), Some(7.25));
+        for amount in [
+            "10 VWRL @ £95",
+            "£5 + £3",
+            "12 USD",
+            "£1,00",
+            "--£4",
+            "£",
+            "1.2.3",
+        ] {
+            assert_eq!(parse_amount(amount), None, "{amount}");
+        }
+
+        let price = "2023-05-01 Buy\n    assets:ISA  10 VWRL @ £95\n    assets:Current\n";
+        assert_eq!(
+            read_journal(price).unwrap_err(),
+            "line 2: bad amount \"10 VWRL @ £95\""
+        );
+    }
+
+    #[test]
+    fn journal_reads_hledger_conventions() {
+        let journal = "\
+; exported elsewhere
+account assets:Current
+
+2023/05/01 * Fish & Chips  ; dinner
+    expenses:Food:Takeaway:Chippy    £12.50
+    assets:Current
+
+2023-05-02 ! Pay day
+    assets:Current\t-£1,000.00
+    income:Income:Salary
+";
+        assert_eq!(
+            read_journal(journal).unwrap(),
+            [
+                transaction(
+                    "Current",
+                    "2023-05-01",
+                    "Fish & Chips",
+                    -12.5,
+                    ["Food", "Takeaway", "Chippy"]
+                ),
+                transaction(
+                    "Current",
+                    "2023-05-02",
+                    "Pay day",
+                    -1000.0,
+                    ["Income", "Salary", ""]
+                ),
+            ]
+        );
+
+        let unbalanced = "2023-05-01 Lunch\n    expenses:Food  £5.00\n";
+        assert_eq!(
+            read_journal(unbalanced).unwrap_err(),
+            "line 1: expected 2 postings, found 1"
+        );
+    }
+}
@@ -1,16 +1,40 @@
 #![warn(clippy::all, clippy::nursery)]
+mod error;
+mod forecast;
+mod goals;
+mod investments;
+mod ledger;
+mod loans;
+mod period;
+mod qif;
+mod tax;
+mod validation;
+
 use std::{
     cmp::{Ord, Ordering},
     collections::HashMap,
     fmt,
 };
 
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
 use serde::{
     de::{self, IntoDeserializer},
     Deserialize, Serialize,
 };
 
+pub use crate::{
+    error::{ApiError, ErrorCode, FieldError},
+    forecast::{Forecast, ForecastOptions, RecurringItem},
+    goals::{Goal, GoalLink, GoalProgress},
+    investments::{Holding, HoldingValue, PricePoint, Trade, TradeKind},
+    ledger::{read_journal, write_journal},
+    loans::{match_repayments, Loan, LoanOptions, LoanStatus, ScheduledPayment},
+    period::PeriodStart,
+    qif::{read_qif, write_qif, QifAccountType, QifOptions},
+    tax::{TaxConfig, TaxLine, TaxYear, TaxYearOptions, TaxYearReport},
+    validation::validate_prices,
+};
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct Transaction {
     pub id: i64,
@@ -21,16 +45,231 @@ pub struct Transaction {
     pub l1_tag: String,
     pub l2_tag: String,
     pub l3_tag: String,
+    #[serde(default)]
+    pub version: i64,
 }
 
 impl Eq for Transaction {}
 
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct TransactionPatch {
+    pub id: i64,
+    pub version: i64,
+    pub account: Option<String>,
+    pub date: Option<NaiveDateTime>,
+    pub description: Option<String>,
+    pub amount: Option<f64>,
+    pub l1_tag: Option<String>,
+    pub l2_tag: Option<String>,
+    pub l3_tag: Option<String>,
+}
+
+impl TransactionPatch {
+    pub fn diff(original: &Transaction, updated: &Transaction) -> Self {
+        fn changed<T: PartialEq + Clone>(original: &T, updated: &T) -> Option<T> {
+            (original != updated).then(|| updated.clone())
+        }
+
+        Self {
+            id: original.id,
+            version: original.version,
+            account: changed(&original.account, &updated.account),
+            date: changed(&original.date, &updated.date),
+            description: changed(&original.description, &updated.description),
+            amount: changed(&original.amount, &updated.amount),
+            l1_tag: changed(&original.l1_tag, &updated.l1_tag),
+            l2_tag: changed(&original.l2_tag, &updated.l2_tag),
+            l3_tag: changed(&original.l3_tag, &updated.l3_tag),
+        }
+    }
+
+    pub fn apply(&self, transaction: &mut Transaction) {
+        if let Some(account) = &self.account {
+            transaction.account = account.clone();
+        }
+        if let Some(date) = self.date {
+            transaction.date = date;
+        }
+        if let Some(description) = &self.description {
+            transaction.description = description.clone();
+        }
+        if let Some(amount) = self.amount {
+            transaction.amount = amount;
+        }
+        if let Some(l1_tag) = &self.l1_tag {
+            transaction.l1_tag = l1_tag.clone();
+        }
+        if let Some(l2_tag) = &self.l2_tag {
+            transaction.l2_tag = l2_tag.clone();
+        }
+        if let Some(l3_tag) = &self.l3_tag {
+            transaction.l3_tag = l3_tag.clone();
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.account.is_none()
+            && self.date.is_none()
+            && self.description.is_none()
+            && self.amount.is_none()
+            && self.l1_tag.is_none()
+            && self.l2_tag.is_none()
+            && self.l3_tag.is_none()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListOptions {
     pub offset: Option<usize>,
     pub limit: Option<usize>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct TransactionFilter {
+    pub account: Option<String>,
+    pub description: Option<String>,
+    pub l1_tag: Option<String>,
+    pub l2_tag: Option<String>,
+    pub l3_tag: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl TransactionFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub enum BulkSelection {
+    Ids(Vec<i64>),
+    Filter(TransactionFilter),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct BulkUpdate {
+    pub account: Option<String>,
+    pub tags: Option<(String, String, String)>,
+    pub description_prefix: Option<String>,
+    pub date_shift: Option<i64>,
+}
+
+impl BulkUpdate {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct BulkEditRequest {
+    pub selection: BulkSelection,
+    pub update: BulkUpdate,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    /* CSV with a byte order mark and CRLF line endings so Excel reads it as UTF-8 */
+    Excel,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 3] = [Self::Csv, Self::Json, Self::Excel];
+
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv | Self::Excel => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv | Self::Excel => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(flatten)]
+    pub filter: TransactionFilter,
+}
+
+impl ExportOptions {
+    pub fn url_encode(&self) -> String {
+        let TransactionFilter {
+            account,
+            description,
+            l1_tag,
+            l2_tag,
+            l3_tag,
+            from,
+            to,
+        } = &self.filter;
+        let mut params = vec![format!("format={:?}", self.format)];
+        let text = [
+            ("account", account),
+            ("description", description),
+            ("l1_tag", l1_tag),
+            ("l2_tag", l2_tag),
+            ("l3_tag", l3_tag),
+        ];
+        for (key, value) in text {
+            if let Some(value) = value {
+                params.push(format!("{key}={}", percent_encode(value)));
+            }
+        }
+        if let Some(from) = from {
+            params.push(format!("from={from:?}"));
+        }
+        if let Some(to) = to {
+            params.push(format!("to={to:?}"));
+        }
+        params.join("&")
+    }
+}
+
+/* Free text like descriptions can hold '&' or '=' */
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/* Each tag is escaped, the commas between them are not */
+fn encode_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| percent_encode(value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub name: String,
+    pub created: NaiveDateTime,
+    pub size: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct RestoreRequest {
+    pub name: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AccountSummary {
     pub name: String,
@@ -64,6 +303,14 @@ pub struct Config {
     period_items: Vec<String>,
     budget_items: Vec<String>,
     tags: Tags,
+    #[serde(default)]
+    period_start: PeriodStart,
+    #[serde(default)]
+    tax: TaxConfig,
+    #[serde(default)]
+    liability_accounts: Vec<String>,
+    #[serde(default)]
+    cash_accounts: Vec<String>,
 }
 
 impl Config {
@@ -86,8 +333,32 @@ impl Config {
     pub const fn tags(&self) -> &Tags {
         &self.tags
     }
+
+    pub const fn period_start(&self) -> PeriodStart {
+        self.period_start
+    }
+
+    pub const fn tax(&self) -> &TaxConfig {
+        &self.tax
+    }
+
+    pub fn liability_accounts(&self) -> &[String] {
+        self.liability_accounts.as_ref()
+    }
+
+    pub fn qif_account_type(&self, account: &str) -> QifAccountType {
+        if self.liability_accounts.iter().any(|a| a == account) {
+            QifAccountType::CreditCard
+        } else if self.cash_accounts.iter().any(|a| a == account) {
+            QifAccountType::Cash
+        } else {
+            QifAccountType::Bank
+        }
+    }
 }
 
+/* Sent once per request, the size of the All variant doesn't matter */
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum ConfigOptions {
     All(Config),
@@ -96,6 +367,7 @@ pub enum ConfigOptions {
     PeriodItems(Vec<String>),
     BudgetItems(Vec<String>),
     Tags(Tags),
+    PeriodStart(PeriodStart),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -128,14 +400,26 @@ pub trait BalancesByDayExt {
     fn cumsum(&self) -> BalanceOverTime;
 
     fn rolling_average_cumsum(&self, window: usize) -> Option<BalanceOverTime>;
+    fn ema_cumsum(&self, span: usize) -> Option<BalanceOverTime>;
+    fn rolling_median_cumsum(&self, window: usize) -> Option<BalanceOverTime>;
+    fn rolling_bands_cumsum(&self, window: usize) -> Option<BalanceBands>;
+    fn fill_gaps(&self) -> Vec<BalanceByTime>;
+    fn resample(&self, grouping: DateGrouping) -> Vec<BalanceByTime>;
 }
 
-#[derive(PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct BalanceOverTime {
     pub dates: Vec<String>,
     pub balances: Vec<f64>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BalanceBands {
+    pub dates: Vec<String>,
+    pub lower: Vec<f64>,
+    pub upper: Vec<f64>,
+}
+
 impl BalancesByDayExt for BalancesByDay {
     fn vectors(&self) -> (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>) {
         let len = self.len();
@@ -225,23 +509,181 @@ impl BalancesByDayExt for BalancesByDay {
             balances: rolling_averages,
         })
     }
+
+    fn ema_cumsum(&self, span: usize) -> Option<BalanceOverTime> {
+        if span == 0 || self.is_empty() {
+            return None;
+        }
+        let alpha = 2.0 / (span as f64 + 1.0);
+        let BalanceOverTime { dates, balances } = self.cumsum();
+
+        let mut ema = Vec::with_capacity(balances.len());
+        let mut previous = balances[0];
+        for balance in balances {
+            previous = alpha * balance + (1.0 - alpha) * previous;
+            ema.push(previous);
+        }
+
+        Some(BalanceOverTime {
+            dates,
+            balances: ema,
+        })
+    }
+
+    fn rolling_median_cumsum(&self, window: usize) -> Option<BalanceOverTime> {
+        let BalanceOverTime { dates, balances } = self.cumsum();
+        let medians = rolling(&balances, window, |w| {
+            let mut sorted = w.to_vec();
+            sorted.sort_by(f64::total_cmp);
+            let mid = sorted.len() / 2;
+            match sorted.len() % 2 {
+                0 => (sorted[mid - 1] + sorted[mid]) / 2.0,
+                _ => sorted[mid],
+            }
+        })?;
+
+        Some(BalanceOverTime {
+            dates: dates[window - 1..].to_vec(),
+            balances: medians,
+        })
+    }
+
+    fn rolling_bands_cumsum(&self, window: usize) -> Option<BalanceBands> {
+        let BalanceOverTime { dates, balances } = self.cumsum();
+        let lower = rolling(&balances, window, |w| {
+            w.iter().copied().fold(f64::INFINITY, f64::min)
+        })?;
+        let upper = rolling(&balances, window, |w| {
+            w.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        })?;
+
+        Some(BalanceBands {
+            dates: dates[window - 1..].to_vec(),
+            lower,
+            upper,
+        })
+    }
+
+    fn fill_gaps(&self) -> Vec<BalanceByTime> {
+        let mut filled: Vec<BalanceByTime> = Vec::with_capacity(self.len());
+        let mut next_day: Option<NaiveDate> = None;
+
+        for point in self {
+            let day = parse_day(&point.date);
+            if let (Some(mut missing), Some(day)) = (next_day, day) {
+                while missing < day {
+                    filled.push(BalanceByTime {
+                        date: missing.format(DAY_FORMAT).to_string(),
+                        ..Default::default()
+                    });
+                    let Some(following) = missing.succ_opt() else {
+                        break;
+                    };
+                    missing = following;
+                }
+            }
+            next_day = day.and_then(|d| d.succ_opt());
+            filled.push(point.clone());
+        }
+
+        filled
+    }
+
+    fn resample(&self, grouping: DateGrouping) -> Vec<BalanceByTime> {
+        let mut resampled: Vec<BalanceByTime> = vec![];
+
+        for point in self {
+            let Some(day) = parse_day(&point.date) else {
+                continue;
+            };
+            let label = grouping.label(day);
+            match resampled.last_mut() {
+                Some(last) if last.date == label => {
+                    last.incoming += point.incoming;
+                    last.outgoing += point.outgoing;
+                    last.balance += point.balance;
+                }
+                _ => resampled.push(BalanceByTime {
+                    date: label,
+                    ..point.clone()
+                }),
+            }
+        }
+
+        resampled
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+fn parse_day(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, DAY_FORMAT).ok()
+}
+
+fn rolling(values: &[f64], window: usize, f: impl Fn(&[f64]) -> f64) -> Option<Vec<f64>> {
+    if window == 0 || values.len() < window {
+        return None;
+    }
+    Some(values.windows(window).map(f).collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum DateGrouping {
+    #[default]
     Day,
+    Week,
     Month,
+    Quarter,
+    Year,
 }
 
 impl DateGrouping {
+    pub const ALL: [Self; 5] = [
+        Self::Day,
+        Self::Week,
+        Self::Month,
+        Self::Quarter,
+        Self::Year,
+    ];
+
     pub fn url_encode(&self) -> String {
         format!("grouping={self:?}")
     }
+
+    /* Matches the period labels the backend groups by */
+    pub fn label(self, day: NaiveDate) -> String {
+        match self {
+            Self::Day => day.format(DAY_FORMAT).to_string(),
+            Self::Week => {
+                let monday =
+                    day - chrono::Duration::days(day.weekday().num_days_from_monday().into());
+                monday.format(DAY_FORMAT).to_string()
+            }
+            Self::Month => day.format("%Y-%m").to_string(),
+            Self::Quarter => format!("{}-Q{}", day.year(), day.month0() / 3 + 1),
+            Self::Year => day.format("%Y").to_string(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct BalanceTimeOptions {
     pub grouping: Option<DateGrouping>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl BalanceTimeOptions {
+    pub fn url_encode(&self) -> String {
+        let mut params = vec![self.grouping.unwrap_or_default().url_encode()];
+        if let Some(from) = self.from {
+            params.push(format!("from={from:?}"));
+        }
+        if let Some(to) = self.to {
+            params.push(format!("to={to:?}"));
+        }
+        params.join("&")
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
@@ -270,17 +712,55 @@ impl BudgetProgressOptions {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagLevel {
+    #[default]
+    L1,
+    L2,
+    L3,
+}
+
+impl TagLevel {
+    pub const fn depth(self) -> usize {
+        match self {
+            Self::L1 => 0,
+            Self::L2 => 1,
+            Self::L3 => 2,
+        }
+    }
+
+    pub const fn from_depth(depth: usize) -> Option<Self> {
+        match depth {
+            0 => Some(Self::L1),
+            1 => Some(Self::L2),
+            2 => Some(Self::L3),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CategorySpendOptions {
     pub date: NaiveDate,
     #[serde(deserialize_with = "deserialize_stringified_list")]
     pub l1_tags: Vec<String>,
+    #[serde(default)]
+    pub level: TagLevel,
+    #[serde(default, deserialize_with = "deserialize_stringified_list")]
+    pub parent: Vec<String>,
 }
 
 impl CategorySpendOptions {
     pub fn url_encode(&self) -> String {
-        let l1_tags = self.l1_tags.join(",");
-        format!("date={:?}&l1_tags={}", self.date, l1_tags)
+        let l1_tags = encode_list(&self.l1_tags);
+        let mut query = format!(
+            "date={:?}&l1_tags={}&level={:?}",
+            self.date, l1_tags, self.level
+        );
+        if !self.parent.is_empty() {
+            query.push_str(&format!("&parent={}", encode_list(&self.parent)));
+        }
+        query
     }
 }
 
@@ -290,6 +770,167 @@ pub struct CategorySpend {
     pub amount: Option<f64>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ComparisonOptions {
+    pub first_from: NaiveDate,
+    pub first_to: NaiveDate,
+    pub second_from: NaiveDate,
+    pub second_to: NaiveDate,
+    #[serde(deserialize_with = "deserialize_stringified_list")]
+    pub l1_tags: Vec<String>,
+}
+
+impl ComparisonOptions {
+    pub fn url_encode(&self) -> String {
+        let l1_tags = encode_list(&self.l1_tags);
+        format!(
+            "first_from={:?}&first_to={:?}&second_from={:?}&second_to={:?}&l1_tags={}",
+            self.first_from, self.first_to, self.second_from, self.second_to, l1_tags
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CategoryComparison {
+    pub name: String,
+    pub first: f64,
+    pub second: f64,
+    pub change: f64,
+    pub percentage_change: Option<f64>,
+}
+
+impl CategoryComparison {
+    pub fn new(name: String, first: f64, second: f64) -> Self {
+        let change = second - first;
+        Self {
+            name,
+            first,
+            second,
+            change,
+            percentage_change: (first != 0.0).then(|| change / first * 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlowOptions {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl FlowOptions {
+    pub fn url_encode(&self) -> String {
+        format!("from={:?}&to={:?}", self.from, self.to)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Flow {
+    pub source: String,
+    pub target: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Kpi {
+    pub period: String,
+    pub income: f64,
+    pub expense: f64,
+    pub savings: f64,
+    pub savings_rate: Option<f64>,
+    pub average_daily_spend: f64,
+}
+
+impl Kpi {
+    pub fn new(period: String, income: f64, expense: f64, days: i64) -> Self {
+        let savings = income - expense;
+        Self {
+            period,
+            income,
+            expense,
+            savings,
+            savings_rate: (income > 0.0).then(|| savings / income),
+            average_daily_spend: expense / days.max(1) as f64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KpiSummary {
+    pub months: Vec<Kpi>,
+    pub trailing_year: Kpi,
+}
+
+/* Income and spending on one day, before grouping into periods */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub income: f64,
+    pub expense: f64,
+}
+
+const KPI_PERIODS: usize = 12;
+
+impl KpiSummary {
+    /* The last twelve financial months, the current one only counts the days so far */
+    pub fn new(period_start: PeriodStart, today: NaiveDate, days: &[DailyTotal]) -> Self {
+        let mut periods = vec![period_start.period(today)];
+        while periods.len() < KPI_PERIODS {
+            let Some(before) = periods[periods.len() - 1].0.pred_opt() else {
+                break;
+            };
+            periods.push(period_start.period(before));
+        }
+        periods.reverse();
+
+        let months: Vec<Kpi> = periods
+            .iter()
+            .map(|&(start, end)| {
+                let last = end.pred_opt().unwrap_or(end).min(today);
+                let (income, expense) = days
+                    .iter()
+                    .filter(|d| (start..=last).contains(&d.date))
+                    .fold((0.0, 0.0), |(income, expense), d| {
+                        (income + d.income, expense + d.expense)
+                    });
+                Kpi::new(
+                    format!("{start} to {last}"),
+                    income,
+                    expense,
+                    (last - start).num_days() + 1,
+                )
+            })
+            .collect();
+
+        let first = periods[0].0;
+        let trailing_year = Kpi::new(
+            format!("{first} to {today}"),
+            months.iter().fold(0.0, |total, k| total + k.income),
+            months.iter().fold(0.0, |total, k| total + k.expense),
+            (today - first).num_days() + 1,
+        );
+        Self {
+            months,
+            trailing_year,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    CategorySpike,
+    LargeTransaction,
+    PossibleDuplicate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub message: String,
+    pub amount: f64,
+    pub transaction_ids: Vec<i64>,
+}
+
 pub fn deserialize_stringified_list<'de, D, I>(
     deserializer: D,
 ) -> std::result::Result<Vec<I>, D::Error>
@@ -324,3 +965,201 @@ where
 
     deserializer.deserialize_any(StringVecVisitor(std::marker::PhantomData::<I>))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, balance: f64) -> BalanceByTime {
+        BalanceByTime {
+            date: date.to_owned(),
+            incoming: balance.max(0.0),
+            outgoing: balance.min(0.0),
+            balance,
+        }
+    }
+
+    fn days() -> Vec<BalanceByTime> {
+        vec![
+            day("2023-01-30", 10.0),
+            day("2023-01-31", -4.0),
+            day("2023-02-02", 6.0),
+            day("2023-02-03", -2.0),
+            day("2023-04-01", 5.0),
+        ]
+    }
+
+    #[test]
+    fn fill_gaps_inserts_empty_days() {
+        let filled = days()[..3].fill_gaps();
+        let dates: Vec<&str> = filled.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(
+            dates,
+            ["2023-01-30", "2023-01-31", "2023-02-01", "2023-02-02"]
+        );
+        assert_eq!(
+            filled[2],
+            BalanceByTime {
+                date: "2023-02-01".to_owned(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn fill_gaps_makes_windows_calendar_days() {
+        let filled = days()[..3].fill_gaps();
+        let ma = filled.rolling_average_cumsum(2).unwrap();
+        assert_eq!(ma.dates, ["2023-01-31", "2023-02-01", "2023-02-02"]);
+        assert_eq!(ma.balances, [8.0, 6.0, 9.0]);
+    }
+
+    #[test]
+    fn ema_starts_at_first_balance() {
+        let ema = days()[..3].ema_cumsum(3).unwrap();
+        /* cumsum is 10, 6, 12 and alpha is 0.5 */
+        assert_eq!(ema.balances, [10.0, 8.0, 10.0]);
+        assert!(days().ema_cumsum(0).is_none());
+    }
+
+    #[test]
+    fn rolling_median_odd_and_even_windows() {
+        /* cumsum is 10, 6, 12, 10, 15 */
+        let median = days().rolling_median_cumsum(3).unwrap();
+        assert_eq!(median.dates, ["2023-02-02", "2023-02-03", "2023-04-01"]);
+        assert_eq!(median.balances, [10.0, 10.0, 12.0]);
+
+        let median = days().rolling_median_cumsum(2).unwrap();
+        assert_eq!(median.balances, [8.0, 9.0, 11.0, 12.5]);
+    }
+
+    #[test]
+    fn rolling_bands_track_min_and_max() {
+        let bands = days().rolling_bands_cumsum(3).unwrap();
+        assert_eq!(bands.dates, ["2023-02-02", "2023-02-03", "2023-04-01"]);
+        assert_eq!(bands.lower, [6.0, 6.0, 10.0]);
+        assert_eq!(bands.upper, [12.0, 12.0, 15.0]);
+    }
+
+    #[test]
+    fn rolling_windows_larger_than_data() {
+        assert!(days().rolling_median_cumsum(6).is_none());
+        assert!(days().rolling_bands_cumsum(0).is_none());
+    }
+
+    #[test]
+    fn resample_to_months_and_quarters() {
+        let months = days().resample(DateGrouping::Month);
+        assert_eq!(
+            months,
+            [
+                BalanceByTime {
+                    date: "2023-01".to_owned(),
+                    incoming: 10.0,
+                    outgoing: -4.0,
+                    balance: 6.0,
+                },
+                BalanceByTime {
+                    date: "2023-02".to_owned(),
+                    incoming: 6.0,
+                    outgoing: -2.0,
+                    balance: 4.0,
+                },
+                day("2023-04", 5.0),
+            ]
+        );
+
+        let quarters = days().resample(DateGrouping::Quarter);
+        let labels: Vec<&str> = quarters.iter().map(|q| q.date.as_str()).collect();
+        assert_eq!(labels, ["2023-Q1", "2023-Q2"]);
+        assert_eq!(quarters[0].balance, 10.0);
+    }
+
+    #[test]
+    fn resample_weeks_start_on_monday() {
+        let weeks = days().resample(DateGrouping::Week);
+        let labels: Vec<&str> = weeks.iter().map(|w| w.date.as_str()).collect();
+        assert_eq!(labels, ["2023-01-30", "2023-03-27"]);
+        assert_eq!(weeks[0].balance, 10.0);
+    }
+
+    #[test]
+    fn kpi_rates_and_averages() {
+        let kpi = Kpi::new("March".to_owned(), 2000.0, 1500.0, 30);
+        assert_eq!(kpi.savings, 500.0);
+        assert_eq!(kpi.savings_rate, Some(0.25));
+        assert_eq!(kpi.average_daily_spend, 50.0);
+
+        let no_income = Kpi::new("April".to_owned(), 0.0, 30.0, 0);
+        assert_eq!(no_income.savings_rate, None);
+        assert_eq!(no_income.average_daily_spend, 30.0);
+    }
+
+    #[test]
+    fn kpi_summary_groups_by_financial_month() {
+        let total = |date: &str, income: f64, expense: f64| DailyTotal {
+            date: parse_day(date).unwrap(),
+            income,
+            expense,
+        };
+        let days = [
+            total("2022-03-24", 0.0, 999.0),
+            total("2023-02-24", 0.0, 100.0),
+            total("2023-02-25", 0.0, 50.0),
+            total("2023-03-01", 2000.0, 0.0),
+            total("2023-03-11", 0.0, 7.0),
+        ];
+        let summary = KpiSummary::new(
+            PeriodStart::Day(25),
+            parse_day("2023-03-10").unwrap(),
+            &days,
+        );
+
+        assert_eq!(summary.months.len(), 12);
+        assert_eq!(summary.months[0].period, "2022-03-25 to 2022-04-24");
+        assert_eq!(summary.months[0].expense, 0.0);
+        assert_eq!(summary.months[10].period, "2023-01-25 to 2023-02-24");
+        assert_eq!(summary.months[10].expense, 100.0);
+
+        /* The current period so far, the 11th is still to come */
+        let current = &summary.months[11];
+        assert_eq!(current.period, "2023-02-25 to 2023-03-10");
+        assert_eq!((current.income, current.expense), (2000.0, 50.0));
+        assert_eq!(current.average_daily_spend, 50.0 / 14.0);
+
+        let year = &summary.trailing_year;
+        assert_eq!(year.period, "2022-03-25 to 2023-03-10");
+        assert_eq!((year.income, year.expense), (2000.0, 150.0));
+        assert_eq!(year.average_daily_spend, 150.0 / 351.0);
+    }
+
+    #[test]
+    fn export_options_escape_free_text() {
+        let options = ExportOptions {
+            format: ExportFormat::Json,
+            filter: TransactionFilter {
+                description: Some("Fish & Chips".to_owned()),
+                from: NaiveDate::from_ymd_opt(2023, 1, 1),
+                ..TransactionFilter::default()
+            },
+        };
+        assert_eq!(
+            options.url_encode(),
+            "format=Json&description=Fish%20%26%20Chips&from=2023-01-01"
+        );
+    }
+
+    #[test]
+    fn category_options_escape_tags() {
+        let options = CategorySpendOptions {
+            date: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+            l1_tags: vec!["Food & Drink".to_owned(), "Travel".to_owned()],
+            level: TagLevel::L3,
+            parent: vec!["Food & Drink".to_owned(), "C#+".to_owned()],
+        };
+        assert_eq!(
+            options.url_encode(),
+            "date=2023-05-01&l1_tags=Food%20%26%20Drink,Travel&level=L3&parent=Food%20%26%20Drink,C%23%2B"
+        );
+    }
+}
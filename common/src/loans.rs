@@ -0,0 +1,252 @@
+use chrono::{Datelike, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{period::last_day_of_month, Transaction};
+
+/* A repayment this many days either side of the due date counts towards it */
+const MATCH_WINDOW_DAYS: i64 = 7;
+/* Anything below a penny is treated as paid off */
+const PAID_OFF: f64 = 0.005;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoanOptions {
+    #[serde(default)]
+    pub extra: f64,
+}
+
+impl LoanOptions {
+    pub fn url_encode(&self) -> String {
+        format!("extra={}", self.extra)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Loan {
+    pub id: i64,
+    pub name: String,
+    pub principal: f64,
+    /* Annual percentage rate */
+    pub rate: f64,
+    pub term_months: u32,
+    pub start_date: NaiveDate,
+    pub payment_day: u32,
+    pub account: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScheduledPayment {
+    pub number: u32,
+    pub date: NaiveDate,
+    pub payment: f64,
+    pub interest: f64,
+    pub principal: f64,
+    pub balance: f64,
+    pub transaction_id: Option<i64>,
+}
+
+impl Loan {
+    fn monthly_rate(&self) -> f64 {
+        self.rate / 100.0 / 12.0
+    }
+
+    pub fn monthly_payment(&self) -> f64 {
+        let rate = self.monthly_rate();
+        let term = f64::from(self.term_months.max(1));
+        if rate == 0.0 {
+            return self.principal / term;
+        }
+        self.principal * rate / (1.0 - (1.0 + rate).powf(-term))
+    }
+
+    pub fn payment_date(&self, number: u32) -> NaiveDate {
+        let month = self
+            .start_date
+            .checked_add_months(Months::new(number))
+            .unwrap_or(self.start_date);
+        let last = last_day_of_month(month.year(), month.month());
+        last.with_day(self.payment_day.clamp(1, last.day()))
+            .unwrap_or(last)
+    }
+
+    pub fn schedule(&self, extra: f64) -> Vec<ScheduledPayment> {
+        self.amortise(self.principal, 1, extra)
+    }
+
+    /* Payments from `first` onwards, starting from the given outstanding balance */
+    fn amortise(&self, balance: f64, first: u32, extra: f64) -> Vec<ScheduledPayment> {
+        let rate = self.monthly_rate();
+        let payment = self.monthly_payment() + extra.max(0.0);
+
+        let mut schedule = vec![];
+        let mut balance = balance;
+        let mut number = first;
+        loop {
+            let interest = balance * rate;
+            if balance <= PAID_OFF || payment <= interest {
+                break;
+            }
+            let principal = (payment - interest).min(balance);
+            balance -= principal;
+            schedule.push(ScheduledPayment {
+                number,
+                date: self.payment_date(number),
+                payment: interest + principal,
+                interest,
+                principal,
+                balance,
+                transaction_id: None,
+            });
+            number += 1;
+        }
+        schedule
+    }
+}
+
+/* Pair each repayment with the closest unmatched scheduled payment */
+pub fn match_repayments(schedule: &mut [ScheduledPayment], repayments: &[Transaction]) {
+    for repayment in repayments {
+        let date = repayment.date.date();
+        let closest = schedule
+            .iter_mut()
+            .filter(|p| p.transaction_id.is_none())
+            .map(|p| ((p.date - date).num_days().abs(), p))
+            .filter(|(distance, _)| *distance <= MATCH_WINDOW_DAYS)
+            .min_by_key(|(distance, _)| *distance);
+        if let Some((_, payment)) = closest {
+            payment.transaction_id = Some(repayment.id);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LoanStatus {
+    pub loan: Loan,
+    pub schedule: Vec<ScheduledPayment>,
+    pub balance: f64,
+    pub interest_paid: f64,
+    pub payments_due: usize,
+    pub payments_matched: usize,
+    pub payoff_date: Option<NaiveDate>,
+    pub remaining_interest: f64,
+    pub baseline_payoff_date: Option<NaiveDate>,
+    pub baseline_remaining_interest: f64,
+}
+
+impl LoanStatus {
+    pub fn new(loan: Loan, repayments: &[Transaction], today: NaiveDate, extra: f64) -> Self {
+        let mut schedule = loan.schedule(0.0);
+        match_repayments(&mut schedule, repayments);
+
+        let due: Vec<&ScheduledPayment> = schedule.iter().filter(|p| p.date <= today).collect();
+        let payments_due = due.len();
+        let payments_matched = due.iter().filter(|p| p.transaction_id.is_some()).count();
+        let interest_due: f64 = due.iter().map(|p| p.interest).sum();
+        let repaid: f64 = repayments.iter().map(|t| t.amount.abs()).sum();
+
+        /* Missed payments leave the balance higher, overpayments bring it down */
+        let balance = (loan.principal + interest_due - repaid).max(0.0);
+        let interest_paid = interest_due.min(repaid);
+
+        let next = u32::try_from(payments_due)
+            .unwrap_or(u32::MAX)
+            .saturating_add(1);
+        let (payoff_date, remaining_interest) =
+            payoff(&loan.amortise(balance, next, extra), balance, today);
+        let (baseline_payoff_date, baseline_remaining_interest) =
+            payoff(&loan.amortise(balance, next, 0.0), balance, today);
+
+        Self {
+            loan,
+            schedule,
+            balance,
+            interest_paid,
+            payments_due,
+            payments_matched,
+            payoff_date,
+            remaining_interest,
+            baseline_payoff_date,
+            baseline_remaining_interest,
+        }
+    }
+
+    pub fn interest_saved(&self) -> f64 {
+        self.baseline_remaining_interest - self.remaining_interest
+    }
+}
+
+fn payoff(
+    schedule: &[ScheduledPayment],
+    balance: f64,
+    today: NaiveDate,
+) -> (Option<NaiveDate>, f64) {
+    if balance <= PAID_OFF {
+        return (Some(today), 0.0);
+    }
+    let interest = schedule.iter().map(|p| p.interest).sum();
+    let date = schedule
+        .last()
+        .filter(|p| p.balance <= PAID_OFF)
+        .map(|p| p.date);
+    (date, interest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loan() -> Loan {
+        Loan {
+            id: 1,
+            name: "Car".to_owned(),
+            principal: 12000.0,
+            rate: 6.0,
+            term_months: 36,
+            start_date: NaiveDate::from_ymd_opt(2023, 1, 15).unwrap(),
+            payment_day: 31,
+            account: "Current".to_owned(),
+        }
+    }
+
+    #[test]
+    fn schedule_pays_off_principal_over_term() {
+        let schedule = loan().schedule(0.0);
+        assert_eq!(schedule.len(), 36);
+        assert_eq!(
+            schedule[0].date,
+            NaiveDate::from_ymd_opt(2023, 2, 28).unwrap()
+        );
+        let repaid: f64 = schedule.iter().map(|p| p.principal).sum();
+        assert!((repaid - 12000.0).abs() < 0.01);
+        assert!((schedule[0].payment - 365.06).abs() < 0.01);
+
+        let faster = loan().schedule(100.0);
+        assert!(faster.len() < 36);
+        let interest = |s: &[ScheduledPayment]| s.iter().map(|p| p.interest).sum::<f64>();
+        assert!(interest(&faster) < interest(&schedule));
+    }
+
+    #[test]
+    fn repayments_match_nearest_scheduled_payment() {
+        let repayment = |id, date: &str| Transaction {
+            id,
+            account: "Current".to_owned(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            description: "Car loan".to_owned(),
+            amount: -365.06,
+            l1_tag: "Repayments".to_owned(),
+            l2_tag: String::new(),
+            l3_tag: String::new(),
+            version: 0,
+        };
+        let mut schedule = loan().schedule(0.0);
+        match_repayments(
+            &mut schedule,
+            &[repayment(7, "2023-03-02"), repayment(8, "2023-05-20")],
+        );
+        assert_eq!(schedule[0].transaction_id, Some(7));
+        assert!(schedule[1..].iter().all(|p| p.transaction_id.is_none()));
+    }
+}
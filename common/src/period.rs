@@ -0,0 +1,160 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodStart {
+    Day(u32),
+    LastWorkingDay,
+}
+
+impl Default for PeriodStart {
+    fn default() -> Self {
+        Self::Day(1)
+    }
+}
+
+impl PeriodStart {
+    /* Start and exclusive end of the financial month containing the date */
+    pub fn period(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let (year, month) = (date.year(), date.month());
+        let start = self.start_in(year, month);
+        if date >= start {
+            let (next_year, next_month) = next_month(year, month);
+            (start, self.start_in(next_year, next_month))
+        } else {
+            let (prev_year, prev_month) = prev_month(year, month);
+            (self.start_in(prev_year, prev_month), start)
+        }
+    }
+
+    pub fn progress(self, date: NaiveDate) -> f64 {
+        let (start, end) = self.period(date);
+        let elapsed = date.signed_duration_since(start).num_days() + 1;
+        let length = end.signed_duration_since(start).num_days();
+        elapsed as f64 / length as f64
+    }
+
+    fn start_in(self, year: i32, month: u32) -> NaiveDate {
+        let last = last_day_of_month(year, month);
+        match self {
+            Self::Day(day) => last.with_day(day.clamp(1, last.day())).unwrap_or(last),
+            Self::LastWorkingDay => match last.weekday() {
+                Weekday::Sat => last - Duration::days(1),
+                Weekday::Sun => last - Duration::days(2),
+                _ => last,
+            },
+        }
+    }
+}
+
+pub fn last_day_of_month(year: i32, month: u32) -> NaiveDate {
+    let (next_year, next_month) = next_month(year, month);
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .expect("valid calendar month")
+}
+
+const fn next_month(year: i32, month: u32) -> (i32, u32) {
+    match month {
+        12 => (year + 1, 1),
+        _ => (year, month + 1),
+    }
+}
+
+const fn prev_month(year: i32, month: u32) -> (i32, u32) {
+    match month {
+        1 => (year - 1, 12),
+        _ => (year, month - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn period(start: PeriodStart, day: &str) -> (NaiveDate, NaiveDate) {
+        start.period(date(day))
+    }
+
+    #[test]
+    fn late_start_clamps_to_end_of_february() {
+        let start = PeriodStart::Day(31);
+        assert_eq!(
+            period(start, "2023-02-15"),
+            (date("2023-01-31"), date("2023-02-28"))
+        );
+        assert_eq!(
+            period(start, "2023-02-28"),
+            (date("2023-02-28"), date("2023-03-31"))
+        );
+        assert_eq!(
+            period(start, "2024-02-28"),
+            (date("2024-01-31"), date("2024-02-29"))
+        );
+        assert_eq!(
+            period(start, "2024-02-29"),
+            (date("2024-02-29"), date("2024-03-31"))
+        );
+    }
+
+    #[test]
+    fn last_working_day_skips_weekends() {
+        let start = PeriodStart::LastWorkingDay;
+        /* 30 September 2023 is a Saturday */
+        assert_eq!(
+            period(start, "2023-09-28"),
+            (date("2023-08-31"), date("2023-09-29"))
+        );
+        assert_eq!(
+            period(start, "2023-09-30"),
+            (date("2023-09-29"), date("2023-10-31"))
+        );
+        /* 31 December 2023 is a Sunday */
+        assert_eq!(
+            period(start, "2023-12-30"),
+            (date("2023-12-29"), date("2024-01-31"))
+        );
+    }
+
+    #[test]
+    fn periods_span_the_new_year() {
+        let start = PeriodStart::Day(25);
+        assert_eq!(
+            period(start, "2023-01-10"),
+            (date("2022-12-25"), date("2023-01-25"))
+        );
+        assert_eq!(
+            period(start, "2023-12-30"),
+            (date("2023-12-25"), date("2024-01-25"))
+        );
+    }
+
+    #[test]
+    fn boundary_dates_start_the_next_period() {
+        let start = PeriodStart::Day(25);
+        assert_eq!(
+            period(start, "2023-01-24"),
+            (date("2022-12-25"), date("2023-01-25"))
+        );
+        assert_eq!(
+            period(start, "2023-01-25"),
+            (date("2023-01-25"), date("2023-02-25"))
+        );
+        assert_eq!(
+            PeriodStart::default().period(date("2023-03-01")),
+            (date("2023-03-01"), date("2023-04-01"))
+        );
+    }
+
+    #[test]
+    fn progress_counts_the_current_day() {
+        let start = PeriodStart::Day(25);
+        assert!((start.progress(date("2023-01-25")) - 1.0 / 31.0).abs() < 1e-9);
+        assert!((start.progress(date("2023-01-24")) - 1.0).abs() < 1e-9);
+        assert!((PeriodStart::default().progress(date("2024-02-15")) - 15.0 / 29.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,465 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::Transaction;
+
+/* Transfers between accounts are written as [Account] categories */
+const TRANSFERS: &str = "Transfers";
+const SPLIT_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QifAccountType {
+    Bank,
+    CreditCard,
+    Cash,
+}
+
+impl QifAccountType {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Bank => "Bank",
+            Self::CreditCard => "CCard",
+            Self::Cash => "Cash",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        [Self::Bank, Self::CreditCard, Self::Cash]
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/* The account for files exported from a single account, which carry no !Account block */
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct QifOptions {
+    pub account: Option<String>,
+}
+
+/* Each account gets an !Account block followed by its transactions. Imported
+ * split lines become separate transactions with nothing to tie them together,
+ * so every transaction is written as its own record */
+pub fn write_qif(
+    transactions: &[Transaction],
+    account_type: impl Fn(&str) -> QifAccountType,
+) -> String {
+    let mut accounts: Vec<(&str, Vec<&Transaction>)> = vec![];
+    for t in transactions {
+        match accounts.iter_mut().find(|(name, _)| *name == t.account) {
+            Some((_, list)) => list.push(t),
+            None => accounts.push((&t.account, vec![t])),
+        }
+    }
+
+    let mut qif = String::new();
+    for (account, list) in accounts {
+        let kind = account_type(account).as_str();
+        qif.push_str(&format!("!Account\nN{account}\nT{kind}\n^\n!Type:{kind}\n"));
+        for t in list {
+            qif.push_str(&format!(
+                "D{}\nT{:.2}\nP{}\n",
+                t.date.format("%d/%m/%Y"),
+                t.amount,
+                t.description
+            ));
+            if let Some(category) = format_category(t) {
+                qif.push_str(&format!("L{category}\n"));
+            }
+            qif.push_str("^\n");
+        }
+    }
+    qif
+}
+
+fn format_category(t: &Transaction) -> Option<String> {
+    /* The class after the account holds l3 */
+    if t.l1_tag == TRANSFERS && !t.l2_tag.is_empty() {
+        return Some(match t.l3_tag.as_str() {
+            "" => format!("[{}]", t.l2_tag),
+            l3_tag => format!("[{}]/{l3_tag}", t.l2_tag),
+        });
+    }
+    /* Tags keep their position, an empty l2 is written as Food::Snacks */
+    let mut tags = vec![t.l1_tag.as_str(), t.l2_tag.as_str(), t.l3_tag.as_str()];
+    while tags.last() == Some(&"") {
+        tags.pop();
+    }
+    (!tags.is_empty()).then(|| tags.join(":"))
+}
+
+#[derive(Default)]
+struct Record {
+    line: usize,
+    date: Option<NaiveDate>,
+    amount: Option<f64>,
+    payee: String,
+    memo: String,
+    category: String,
+    splits: Vec<Split>,
+}
+
+#[derive(Default)]
+struct Split {
+    category: String,
+    memo: String,
+    amount: Option<f64>,
+}
+
+enum Section {
+    Account,
+    Transactions,
+    /* Investment, memorised and category lists are skipped */
+    Other,
+}
+
+pub fn read_qif(qif: &str, default_account: Option<&str>) -> Result<Vec<Transaction>, String> {
+    let mut transactions = vec![];
+    let mut section = Section::Other;
+    let mut account = default_account.map(str::to_owned);
+    let mut record = Record::default();
+
+    for (i, raw) in qif.lines().enumerate() {
+        let line = i + 1;
+        let text = raw.trim_end();
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = text.strip_prefix('!') {
+            if header.eq_ignore_ascii_case("Account") {
+                section = Section::Account;
+            } else if let Some(kind) = header.strip_prefix("Type:") {
+                section = match QifAccountType::parse(kind) {
+                    Some(_) => Section::Transactions,
+                    None => Section::Other,
+                };
+            }
+            continue;
+        }
+
+        let (code, value) = text.split_at(text.chars().next().map_or(0, char::len_utf8));
+        match section {
+            Section::Other => {}
+            Section::Account => match code {
+                "N" => account = Some(value.trim().to_owned()),
+                "^" => section = Section::Other,
+                _ => {}
+            },
+            Section::Transactions => {
+                if record.line == 0 {
+                    record.line = line;
+                }
+                match code {
+                    "D" => record.date = Some(parse_date(value, line)?),
+                    "T" | "U" => record.amount = Some(parse_amount(value, line)?),
+                    "P" => record.payee = value.trim().to_owned(),
+                    "M" => record.memo = value.trim().to_owned(),
+                    "L" => record.category = value.trim().to_owned(),
+                    "S" => record.splits.push(Split {
+                        category: value.trim().to_owned(),
+                        ..Split::default()
+                    }),
+                    "E" => {
+                        if let Some(split) = record.splits.last_mut() {
+                            split.memo = value.trim().to_owned();
+                        }
+                    }
+                    "$" => {
+                        if let Some(split) = record.splits.last_mut() {
+                            split.amount = Some(parse_amount(value, line)?);
+                        }
+                    }
+                    "^" => {
+                        let Some(account) = &account else {
+                            return Err(format!("line {line}: no account for transaction"));
+                        };
+                        into_transactions(std::mem::take(&mut record), account, &mut transactions)?;
+                    }
+                    /* Check numbers, cleared status and addresses */
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /* The last record may end at the end of the file without a ^ */
+    if record.line != 0 {
+        let Some(account) = &account else {
+            return Err(format!("line {}: no account for transaction", record.line));
+        };
+        into_transactions(record, account, &mut transactions)?;
+    }
+
+    Ok(transactions)
+}
+
+fn into_transactions(
+    record: Record,
+    account: &str,
+    transactions: &mut Vec<Transaction>,
+) -> Result<(), String> {
+    let Record {
+        line,
+        date,
+        amount,
+        payee,
+        memo,
+        category,
+        splits,
+    } = record;
+    let Some(date) = date else {
+        return Err(format!("line {line}: transaction has no date"));
+    };
+    let Some(amount) = amount else {
+        return Err(format!("line {line}: transaction has no amount"));
+    };
+    let description = if payee.is_empty() { memo } else { payee };
+    let transaction = |category: &str, description: &str, amount: f64| {
+        let [l1_tag, l2_tag, l3_tag] = parse_category(category);
+        Transaction {
+            id: 0,
+            account: account.to_owned(),
+            date: NaiveDateTime::new(date, NaiveTime::default()),
+            description: description.to_owned(),
+            amount,
+            l1_tag,
+            l2_tag,
+            l3_tag,
+            version: 0,
+        }
+    };
+
+    if splits.is_empty() {
+        transactions.push(transaction(&category, &description, amount));
+        return Ok(());
+    }
+
+    let mut total = 0.0;
+    for split in splits {
+        let Some(split_amount) = split.amount else {
+            return Err(format!(
+                "line {line}: split {} has no amount",
+                split.category
+            ));
+        };
+        total += split_amount;
+        let description = if description.is_empty() {
+            &split.memo
+        } else {
+            &description
+        };
+        transactions.push(transaction(&split.category, description, split_amount));
+    }
+    if (total - amount).abs() > SPLIT_TOLERANCE {
+        return Err(format!(
+            "line {line}: splits total {total:.2} but the transaction is {amount:.2}"
+        ));
+    }
+    Ok(())
+}
+
+/* Category:Sub:Sub/Class, the class is dropped except on transfers */
+fn parse_category(category: &str) -> [String; 3] {
+    let (category, class) = category.split_once('/').unwrap_or((category, ""));
+    if let Some(account) = category.strip_prefix('[').and_then(|c| c.strip_suffix(']')) {
+        return [TRANSFERS.to_owned(), account.to_owned(), class.to_owned()];
+    }
+    let mut tags = category.splitn(3, ':').map(str::to_owned);
+    [(); 3].map(|_| tags.next().unwrap_or_default())
+}
+
+/* Day first as written by UK tools, with the 2 digit year forms such as 1/5'23 */
+fn parse_date(date: &str, line: usize) -> Result<NaiveDate, String> {
+    let error = || format!("line {line}: bad date {date:?}");
+    let date = date.trim();
+    if let Ok(d) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(d);
+    }
+
+    let parts: Vec<u32> = date
+        .split(&['/', '\'', '-'][..])
+        .map(|p| p.trim().parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| error())?;
+    let [day, month, year] = <[u32; 3]>::try_from(parts).map_err(|_| error())?;
+    let year = match year {
+        0..=69 => year + 2000,
+        70..=99 => year + 1900,
+        _ => year,
+    };
+    let year = i32::try_from(year).map_err(|_| error())?;
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(error)
+}
+
+fn parse_amount(amount: &str, line: usize) -> Result<f64, String> {
+    amount
+        .trim()
+        .replace(',', "")
+        .parse::<f64>()
+        .map_err(|_| format!("line {line}: bad amount {:?}", amount.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(
+        account: &str,
+        date: &str,
+        description: &str,
+        amount: f64,
+        tags: [&str; 3],
+    ) -> Transaction {
+        Transaction {
+            id: 0,
+            account: account.to_owned(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            description: description.to_owned(),
+            amount,
+            l1_tag: tags[0].to_owned(),
+            l2_tag: tags[1].to_owned(),
+            l3_tag: tags[2].to_owned(),
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn qif_round_trips() {
+        let transactions = vec![
+            transaction(
+                "Current",
+                "2023-05-01",
+                "Fish & Chips",
+                -12.5,
+                ["Food", "Takeaway", "Chippy"],
+            ),
+            transaction(
+                "Current",
+                "2023-05-02",
+                "To savings",
+                -100.0,
+                ["Transfers", "Savings", ""],
+            ),
+            transaction(
+                "Current",
+                "2023-05-02",
+                "To ISA",
+                -50.0,
+                ["Transfers", "ISA", "Regular"],
+            ),
+            transaction(
+                "Credit Card",
+                "2023-05-03",
+                "Refund",
+                1234.56,
+                ["Shopping", "", ""],
+            ),
+        ];
+        let account_type = |account: &str| {
+            if account == "Credit Card" {
+                QifAccountType::CreditCard
+            } else {
+                QifAccountType::Bank
+            }
+        };
+
+        let qif = write_qif(&transactions, account_type);
+        assert!(qif.contains("!Account\nNCredit Card\nTCCard\n^\n!Type:CCard\n"));
+        assert!(qif.contains("D02/05/2023\nT-100.00\nPTo savings\nL[Savings]\n^\n"));
+        assert!(qif.contains("L[ISA]/Regular\n"));
+        assert_eq!(read_qif(&qif, None).unwrap(), transactions);
+    }
+
+    #[test]
+    fn qif_reads_splits_into_transactions() {
+        let qif = "\
+!Type:Cash
+D 1/ 5'23
+U-30.00
+T-30.00
+PSupermarket
+LFood
+SFood:Groceries
+$-20.00
+SHousehold:Cleaning/Home
+EBleach
+$-10.00
+^
+";
+        assert_eq!(
+            read_qif(qif, Some("Wallet")).unwrap(),
+            [
+                transaction(
+                    "Wallet",
+                    "2023-05-01",
+                    "Supermarket",
+                    -20.0,
+                    ["Food", "Groceries", ""]
+                ),
+                transaction(
+                    "Wallet",
+                    "2023-05-01",
+                    "Supermarket",
+                    -10.0,
+                    ["Household", "Cleaning", ""]
+                ),
+            ]
+        );
+        assert_eq!(
+            read_qif(qif, None).unwrap_err(),
+            "line 12: no account for transaction"
+        );
+        assert_eq!(
+            read_qif(&qif.replace("$-10.00", "$-9.00"), Some("Wallet")).unwrap_err(),
+            "line 2: splits total -29.00 but the transaction is -30.00"
+        );
+    }
+
+    #[test]
+    fn qif_keeps_same_day_purchases_apart() {
+        let transactions = vec![
+            transaction(
+                "Current",
+                "2023-05-01",
+                "TESCO",
+                -20.0,
+                ["Food", "Groceries", ""],
+            ),
+            transaction(
+                "Current",
+                "2023-05-01",
+                "TESCO",
+                -10.0,
+                ["Household", "", "Cleaning"],
+            ),
+        ];
+
+        let qif = write_qif(&transactions, |_| QifAccountType::Bank);
+        assert!(qif.contains("T-20.00\nPTESCO\nLFood:Groceries\n^\n"));
+        assert!(qif.contains("T-10.00\nPTESCO\nLHousehold::Cleaning\n^\n"));
+        assert!(!qif.contains("\nS"));
+        assert_eq!(read_qif(&qif, None).unwrap(), transactions);
+    }
+
+    #[test]
+    fn qif_reads_a_last_record_without_caret() {
+        let qif = "!Type:Bank\nD01/05/2023\nT-4.50\nPCafe\nLFood\n";
+        assert_eq!(
+            read_qif(qif, Some("Current")).unwrap(),
+            [transaction(
+                "Current",
+                "2023-05-01",
+                "Cafe",
+                -4.5,
+                ["Food", "", ""]
+            )]
+        );
+        assert_eq!(
+            read_qif(qif, None).unwrap_err(),
+            "line 2: no account for transaction"
+        );
+    }
+}
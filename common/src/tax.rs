@@ -0,0 +1,197 @@
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/* UK tax years start on 6 April and are named by the calendar year they start in */
+const START_MONTH: u32 = 4;
+const START_DAY: u32 = 6;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaxYear(pub i32);
+
+impl TaxYear {
+    pub fn containing(date: NaiveDate) -> Self {
+        if (date.month(), date.day()) >= (START_MONTH, START_DAY) {
+            Self(date.year())
+        } else {
+            Self(date.year() - 1)
+        }
+    }
+
+    /* Out of range years come from user input, so fall back rather than panic */
+    pub fn start(self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.0, START_MONTH, START_DAY).unwrap_or_default()
+    }
+
+    /* Exclusive, the start of the following tax year */
+    pub fn end(self) -> NaiveDate {
+        self.next().start()
+    }
+
+    /* Stays put at the ends of i32, those years have no real dates anyway */
+    pub const fn next(self) -> Self {
+        match self.0.checked_add(1) {
+            Some(year) => Self(year),
+            None => self,
+        }
+    }
+
+    pub const fn prev(self) -> Self {
+        match self.0.checked_sub(1) {
+            Some(year) => Self(year),
+            None => self,
+        }
+    }
+}
+
+impl fmt::Display for TaxYear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{:02}", self.0, self.next().0 % 100)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaxYearOptions {
+    pub year: i32,
+}
+
+impl TaxYearOptions {
+    pub fn url_encode(&self) -> String {
+        format!("year={}", self.year)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TaxConfig {
+    pub interest_tags: Vec<String>,
+    pub pension_tags: Vec<String>,
+    pub deductible_tags: Vec<String>,
+}
+
+impl Default for TaxConfig {
+    fn default() -> Self {
+        Self {
+            interest_tags: vec!["Interest".to_owned()],
+            pension_tags: vec!["Pension".to_owned()],
+            deductible_tags: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TaxLine {
+    pub name: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TaxYearReport {
+    pub year: TaxYear,
+    pub income: Vec<TaxLine>,
+    pub interest: f64,
+    pub dividends: f64,
+    pub pension_contributions: f64,
+    pub deductible: Vec<TaxLine>,
+}
+
+impl TaxYearReport {
+    pub fn total_income(&self) -> f64 {
+        self.income.iter().map(|l| l.amount).sum::<f64>() + self.interest + self.dividends
+    }
+
+    pub fn total_deductible(&self) -> f64 {
+        /* An empty f64 sum is -0.0, which would print as -0.00 */
+        self.deductible.iter().map(|l| l.amount).sum::<f64>() + 0.0
+    }
+
+    /* Flattened as section, name, amount for spreadsheets */
+    pub fn rows(&self) -> Vec<(&'static str, String, f64)> {
+        let mut rows: Vec<_> = self
+            .income
+            .iter()
+            .map(|l| ("Income", l.name.clone(), l.amount))
+            .collect();
+        rows.push(("Income", "Interest".to_owned(), self.interest));
+        rows.push(("Income", "Dividends".to_owned(), self.dividends));
+        rows.push(("Income", "Total".to_owned(), self.total_income()));
+        rows.push((
+            "Pension",
+            "Contributions".to_owned(),
+            self.pension_contributions,
+        ));
+        rows.extend(
+            self.deductible
+                .iter()
+                .map(|l| ("Deductible", l.name.clone(), l.amount)),
+        );
+        rows.push(("Deductible", "Total".to_owned(), self.total_deductible()));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn line(name: &str, amount: f64) -> TaxLine {
+        TaxLine {
+            name: name.to_owned(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn tax_year_runs_from_sixth_of_april() {
+        assert_eq!(TaxYear::containing(date("2024-04-05")), TaxYear(2023));
+        assert_eq!(TaxYear::containing(date("2024-04-06")), TaxYear(2024));
+        assert_eq!(TaxYear(2023).end(), date("2024-04-06"));
+        assert_eq!(TaxYear(2023).to_string(), "2023/24");
+        assert_eq!(TaxYear(1999).to_string(), "1999/00");
+    }
+
+    #[test]
+    fn extreme_years_do_not_overflow() {
+        assert_eq!(TaxYear(i32::MAX).next(), TaxYear(i32::MAX));
+        assert_eq!(TaxYear(i32::MIN).prev(), TaxYear(i32::MIN));
+        assert_eq!(TaxYear(i32::MAX).end(), NaiveDate::default());
+        assert_eq!(TaxYear(i32::MAX).to_string(), "2147483647/47");
+    }
+
+    #[test]
+    fn totals_add_every_line() {
+        let report = TaxYearReport {
+            year: TaxYear(2023),
+            income: vec![line("Salary", 30_000.0), line("Freelance", 2_000.0)],
+            interest: 150.0,
+            dividends: 50.0,
+            pension_contributions: 1_500.0,
+            deductible: vec![line("Tools", 120.0), line("Travel", 80.0)],
+        };
+        assert_eq!(report.total_income(), 32_200.0);
+        assert_eq!(report.total_deductible(), 200.0);
+
+        let rows = report.rows();
+        assert_eq!(rows.len(), 9);
+        assert_eq!(rows[4], ("Income", "Total".to_owned(), 32_200.0));
+        assert_eq!(rows[8], ("Deductible", "Total".to_owned(), 200.0));
+    }
+
+    #[test]
+    fn empty_totals_are_not_negative_zero() {
+        let report = TaxYearReport {
+            year: TaxYear(2023),
+            income: vec![],
+            interest: 0.0,
+            dividends: 0.0,
+            pension_contributions: 0.0,
+            deductible: vec![],
+        };
+        assert_eq!(format!("{:.2}", report.total_income()), "0.00");
+        assert_eq!(format!("{:.2}", report.total_deductible()), "0.00");
+    }
+}
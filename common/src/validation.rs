@@ -0,0 +1,563 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::{
+    ApiError, BulkUpdate, ComparisonOptions, Config, FieldError, Goal, GoalLink, Loan, PricePoint,
+    TaxYearOptions, Trade, TradeKind, Transaction,
+};
+
+const EARLIEST_YEAR: i32 = 1900;
+const LATEST_YEAR: i32 = 2100;
+/* A century either way, further than any real correction */
+const MAX_DATE_SHIFT_DAYS: i64 = 36_525;
+const MAX_TERM_MONTHS: u32 = 600;
+
+impl Config {
+    pub fn validate_transaction(&self, transaction: &Transaction) -> Result<(), ApiError> {
+        let mut field_errors = vec![];
+
+        if let Some(e) = self.account_error(&transaction.account) {
+            field_errors.push(e);
+        }
+
+        let year = transaction.date.year();
+        if !(EARLIEST_YEAR..=LATEST_YEAR).contains(&year) {
+            field_errors.push(FieldError::new(
+                "date",
+                format!("Date {} is out of range.", transaction.date.date()),
+            ));
+        }
+
+        if !transaction.amount.is_finite() {
+            field_errors.push(FieldError::new("amount", "Amount must be a number."));
+        }
+
+        if let Some(e) = self.tags_error(
+            &transaction.l1_tag,
+            &transaction.l2_tag,
+            &transaction.l3_tag,
+        ) {
+            field_errors.push(e);
+        }
+
+        into_result(field_errors)
+    }
+
+    pub fn validate_bulk_update(&self, update: &BulkUpdate) -> Result<(), ApiError> {
+        let mut field_errors = vec![];
+
+        if let Some(e) = update
+            .account
+            .as_ref()
+            .and_then(|account| self.account_error(account))
+        {
+            field_errors.push(e);
+        }
+
+        if let Some(e) = update
+            .tags
+            .as_ref()
+            .and_then(|(l1_tag, l2_tag, l3_tag)| self.tags_error(l1_tag, l2_tag, l3_tag))
+        {
+            field_errors.push(e);
+        }
+
+        if update
+            .date_shift
+            .is_some_and(|days| days.abs() > MAX_DATE_SHIFT_DAYS)
+        {
+            field_errors.push(FieldError::new(
+                "date_shift",
+                format!("Date shift must be within {MAX_DATE_SHIFT_DAYS} days."),
+            ));
+        }
+
+        into_result(field_errors)
+    }
+
+    pub fn validate_trade(&self, trade: &Trade) -> Result<(), ApiError> {
+        let mut field_errors = match self.validate_transaction(&trade.transaction) {
+            Ok(()) => vec![],
+            Err(e) => e.field_errors,
+        };
+
+        if trade.ticker.trim().is_empty() {
+            field_errors.push(FieldError::new("ticker", "Ticker is required."));
+        }
+
+        let needs_quantity = trade.kind != TradeKind::Dividend;
+        if needs_quantity && (!trade.quantity.is_finite() || trade.quantity <= 0.0) {
+            field_errors.push(FieldError::new("quantity", "Quantity must be above zero."));
+        }
+
+        into_result(field_errors)
+    }
+
+    pub fn validate_goal(&self, goal: &Goal) -> Result<(), ApiError> {
+        let mut field_errors = vec![];
+
+        if goal.name.trim().is_empty() {
+            field_errors.push(FieldError::new("name", "Name is required."));
+        }
+
+        if !goal.target.is_finite() || goal.target <= 0.0 {
+            field_errors.push(FieldError::new("target", "Target must be above zero."));
+        }
+
+        let year = goal.target_date.year();
+        if !(EARLIEST_YEAR..=LATEST_YEAR).contains(&year) {
+            field_errors.push(FieldError::new(
+                "target_date",
+                format!("Date {} is out of range.", goal.target_date),
+            ));
+        }
+
+        match &goal.link {
+            GoalLink::Account(account) => {
+                if let Some(e) = self.account_error(account) {
+                    field_errors.push(e);
+                }
+            }
+            GoalLink::Tag(tag) if tag.trim().is_empty() => {
+                field_errors.push(FieldError::new("tag", "Tag is required."));
+            }
+            GoalLink::Tag(_) => (),
+        }
+
+        into_result(field_errors)
+    }
+
+    pub fn validate_loan(&self, loan: &Loan) -> Result<(), ApiError> {
+        let mut field_errors = vec![];
+
+        if loan.name.trim().is_empty() {
+            field_errors.push(FieldError::new("name", "Name is required."));
+        }
+
+        if !loan.principal.is_finite() || loan.principal <= 0.0 {
+            field_errors.push(FieldError::new(
+                "principal",
+                "Principal must be above zero.",
+            ));
+        }
+
+        if !loan.rate.is_finite() || loan.rate < 0.0 {
+            field_errors.push(FieldError::new("rate", "Rate cannot be negative."));
+        }
+
+        if !(1..=MAX_TERM_MONTHS).contains(&loan.term_months) {
+            field_errors.push(FieldError::new(
+                "term_months",
+                format!("Term must be between 1 and {MAX_TERM_MONTHS} months."),
+            ));
+        }
+
+        if !(1..=31).contains(&loan.payment_day) {
+            field_errors.push(FieldError::new(
+                "payment_day",
+                "Payment day must be between 1 and 31.",
+            ));
+        }
+
+        let year = loan.start_date.year();
+        if !(EARLIEST_YEAR..=LATEST_YEAR).contains(&year) {
+            field_errors.push(FieldError::new(
+                "start_date",
+                format!("Date {} is out of range.", loan.start_date),
+            ));
+        }
+
+        if let Some(e) = self.account_error(&loan.account) {
+            field_errors.push(e);
+        }
+
+        into_result(field_errors)
+    }
+
+    fn account_error(&self, account: &str) -> Option<FieldError> {
+        if self.account_list().iter().any(|a| a == account) {
+            return None;
+        }
+        Some(FieldError::new(
+            "account",
+            format!("Unknown account {account:?}."),
+        ))
+    }
+
+    fn tags_error(&self, l1_tag: &str, l2_tag: &str, l3_tag: &str) -> Option<FieldError> {
+        if self.tags().verify_tags(l1_tag, l2_tag, l3_tag) {
+            return None;
+        }
+        Some(FieldError::new(
+            "tags",
+            format!("Unknown tags {l1_tag:?}, {l2_tag:?}, {l3_tag:?}."),
+        ))
+    }
+}
+
+impl BulkUpdate {
+    /* The selected rows are only known to the backend, which passes their
+     * earliest and latest dates */
+    pub fn validate_shifted_dates(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+    ) -> Result<(), ApiError> {
+        let Some(days) = self.date_shift else {
+            return Ok(());
+        };
+        let in_range = |date: NaiveDate| {
+            days.abs() <= MAX_DATE_SHIFT_DAYS
+                && date
+                    .checked_add_signed(Duration::days(days))
+                    .is_some_and(|date| (EARLIEST_YEAR..=LATEST_YEAR).contains(&date.year()))
+        };
+        if in_range(first) && in_range(last) {
+            return Ok(());
+        }
+        into_result(vec![FieldError::new(
+            "date_shift",
+            format!(
+                "Shifting by {days} days moves dates outside {EARLIEST_YEAR} to {LATEST_YEAR}."
+            ),
+        )])
+    }
+}
+
+impl ComparisonOptions {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut field_errors = vec![];
+        for (field, from, to) in [
+            ("first_to", self.first_from, self.first_to),
+            ("second_to", self.second_from, self.second_to),
+        ] {
+            if from > to {
+                field_errors.push(FieldError::new(
+                    field,
+                    format!("Range ends on {to}, before it starts on {from}."),
+                ));
+            }
+        }
+        into_result(field_errors)
+    }
+}
+
+impl TaxYearOptions {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if (EARLIEST_YEAR..=LATEST_YEAR).contains(&self.year) {
+            return Ok(());
+        }
+        Err(ApiError::bad_request(format!(
+            "Tax year {} is out of range.",
+            self.year
+        )))
+    }
+}
+
+/* Rows are numbered as in the file, after its header */
+pub fn validate_prices(prices: &[PricePoint]) -> Result<(), ApiError> {
+    let mut field_errors = vec![];
+    for (i, price) in prices.iter().enumerate() {
+        let row = i + 2;
+        if price.ticker.trim().is_empty() {
+            field_errors.push(FieldError::new(
+                "ticker",
+                format!("Row {row}: ticker is required."),
+            ));
+        }
+        if !price.price.is_finite() || price.price <= 0.0 {
+            field_errors.push(FieldError::new(
+                "price",
+                format!("Row {row}: price must be above zero."),
+            ));
+        }
+    }
+    into_result(field_errors)
+}
+
+fn into_result(field_errors: Vec<FieldError>) -> Result<(), ApiError> {
+    if field_errors.is_empty() {
+        return Ok(());
+    }
+    Err(ApiError::validation(field_errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{ErrorCode, PeriodStart, Tags, TaxConfig};
+
+    fn config() -> Config {
+        let tags = HashMap::from([(
+            "Food".to_owned(),
+            HashMap::from([("Groceries".to_owned(), vec![String::new()])]),
+        )]);
+        Config {
+            budget: 100.0,
+            account_list: vec!["Current".to_owned()],
+            period_items: vec![],
+            budget_items: vec![],
+            tags: Tags(tags),
+            period_start: PeriodStart::default(),
+            tax: TaxConfig::default(),
+            liability_accounts: vec![],
+            cash_accounts: vec![],
+        }
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn transaction() -> Transaction {
+        Transaction {
+            account: "Current".to_owned(),
+            date: date("2023-03-01").and_hms_opt(0, 0, 0).unwrap(),
+            description: "Shop".to_owned(),
+            amount: -12.5,
+            l1_tag: "Food".to_owned(),
+            l2_tag: "Groceries".to_owned(),
+            ..Transaction::default()
+        }
+    }
+
+    fn fields(result: Result<(), ApiError>) -> Vec<String> {
+        result
+            .err()
+            .map(|e| e.field_errors.into_iter().map(|f| f.field).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn transaction_checks_account_date_amount_and_tags() {
+        let config = config();
+        assert!(config.validate_transaction(&transaction()).is_ok());
+
+        let bad = Transaction {
+            account: "Savings".to_owned(),
+            date: date("1850-01-01").and_hms_opt(0, 0, 0).unwrap(),
+            amount: f64::NAN,
+            l2_tag: "Takeaway".to_owned(),
+            ..transaction()
+        };
+        assert_eq!(
+            fields(config.validate_transaction(&bad)),
+            ["account", "date", "amount", "tags"]
+        );
+    }
+
+    #[test]
+    fn bulk_update_bounds_date_shift() {
+        let config = config();
+        let update = |days| BulkUpdate {
+            date_shift: Some(days),
+            ..BulkUpdate::default()
+        };
+        assert!(config.validate_bulk_update(&update(-7)).is_ok());
+        assert!(config
+            .validate_bulk_update(&update(MAX_DATE_SHIFT_DAYS))
+            .is_ok());
+        assert_eq!(
+            fields(config.validate_bulk_update(&update(i64::MAX))),
+            ["date_shift"]
+        );
+
+        let bad = BulkUpdate {
+            account: Some("Savings".to_owned()),
+            tags: Some(("Food".to_owned(), String::new(), String::new())),
+            ..BulkUpdate::default()
+        };
+        assert_eq!(
+            fields(config.validate_bulk_update(&bad)),
+            ["account", "tags"]
+        );
+    }
+
+    #[test]
+    fn shifted_dates_stay_in_range() {
+        let update = |days| BulkUpdate {
+            date_shift: Some(days),
+            ..BulkUpdate::default()
+        };
+        let (first, last) = (date("1900-01-05"), date("2100-12-20"));
+        assert!(update(4).validate_shifted_dates(first, last).is_ok());
+        assert!(update(-4).validate_shifted_dates(first, last).is_ok());
+        assert_eq!(
+            fields(update(-5).validate_shifted_dates(first, last)),
+            ["date_shift"]
+        );
+        assert_eq!(
+            fields(update(12).validate_shifted_dates(first, last)),
+            ["date_shift"]
+        );
+        assert!(BulkUpdate::default()
+            .validate_shifted_dates(first, last)
+            .is_ok());
+    }
+
+    #[test]
+    fn tax_year_must_be_in_range() {
+        let options = |year| TaxYearOptions { year };
+        assert!(options(2023).validate().is_ok());
+        assert_eq!(
+            options(i32::MAX).validate().unwrap_err().code,
+            ErrorCode::BadRequest
+        );
+        assert!(options(1899).validate().is_err());
+    }
+
+    #[test]
+    fn trade_needs_ticker_and_quantity_except_dividends() {
+        let config = config();
+        let trade = |kind, quantity| Trade {
+            kind,
+            ticker: "VWRL".to_owned(),
+            quantity,
+            transaction: transaction(),
+        };
+        assert!(config.validate_trade(&trade(TradeKind::Buy, 2.0)).is_ok());
+        assert!(config
+            .validate_trade(&trade(TradeKind::Dividend, 0.0))
+            .is_ok());
+        assert_eq!(
+            fields(config.validate_trade(&trade(TradeKind::Sell, 0.0))),
+            ["quantity"]
+        );
+
+        let bad = Trade {
+            ticker: " ".to_owned(),
+            ..trade(TradeKind::Buy, f64::INFINITY)
+        };
+        assert_eq!(fields(config.validate_trade(&bad)), ["ticker", "quantity"]);
+    }
+
+    #[test]
+    fn prices_must_be_positive_numbers() {
+        let price = |ticker: &str, price| PricePoint {
+            date: date("2023-03-01"),
+            ticker: ticker.to_owned(),
+            price,
+        };
+        assert!(validate_prices(&[price("VWRL", 95.5)]).is_ok());
+
+        let error = validate_prices(&[
+            price("VWRL", 95.5),
+            price("VWRL", f64::NAN),
+            price("", 0.0),
+            price("VUSA", -1.0),
+        ])
+        .unwrap_err();
+        let messages: Vec<&str> = error
+            .field_errors
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Row 3: price must be above zero.",
+                "Row 4: ticker is required.",
+                "Row 4: price must be above zero.",
+                "Row 5: price must be above zero.",
+            ]
+        );
+    }
+
+    #[test]
+    fn goal_checks_name_target_date_and_link() {
+        let config = config();
+        let goal = Goal {
+            id: 0,
+            name: "Holiday".to_owned(),
+            target: 1000.0,
+            target_date: date("2024-06-01"),
+            link: GoalLink::Account("Current".to_owned()),
+        };
+        assert!(config.validate_goal(&goal).is_ok());
+
+        let bad = Goal {
+            name: String::new(),
+            target: 0.0,
+            target_date: date("2200-01-01"),
+            link: GoalLink::Tag(" ".to_owned()),
+            ..goal
+        };
+        assert_eq!(
+            fields(config.validate_goal(&bad)),
+            ["name", "target", "target_date", "tag"]
+        );
+
+        let unknown = Goal {
+            link: GoalLink::Account("Savings".to_owned()),
+            ..goal
+        };
+        assert_eq!(fields(config.validate_goal(&unknown)), ["account"]);
+    }
+
+    #[test]
+    fn comparison_ranges_must_not_be_reversed() {
+        let options = ComparisonOptions {
+            first_from: date("2023-01-01"),
+            first_to: date("2023-01-31"),
+            second_from: date("2023-02-01"),
+            second_to: date("2023-02-01"),
+            l1_tags: vec![],
+        };
+        assert!(options.validate().is_ok());
+
+        let reversed = ComparisonOptions {
+            first_from: date("2023-02-01"),
+            second_to: date("2023-01-31"),
+            ..options
+        };
+        assert_eq!(fields(reversed.validate()), ["first_to", "second_to"]);
+    }
+
+    #[test]
+    fn loan_checks_every_field() {
+        let config = config();
+        let loan = Loan {
+            id: 0,
+            name: "Car".to_owned(),
+            principal: 10_000.0,
+            rate: 5.0,
+            term_months: 36,
+            start_date: date("2023-01-01"),
+            payment_day: 1,
+            account: "Current".to_owned(),
+        };
+        assert!(config.validate_loan(&loan).is_ok());
+
+        let endless = Loan {
+            term_months: u32::MAX,
+            ..loan
+        };
+        assert_eq!(fields(config.validate_loan(&endless)), ["term_months"]);
+
+        let bad = Loan {
+            id: 0,
+            name: String::new(),
+            principal: -1.0,
+            rate: -0.5,
+            term_months: 0,
+            start_date: date("1800-01-01"),
+            payment_day: 32,
+            account: "Savings".to_owned(),
+        };
+        assert_eq!(
+            fields(config.validate_loan(&bad)),
+            [
+                "name",
+                "principal",
+                "rate",
+                "term_months",
+                "payment_day",
+                "start_date",
+                "account"
+            ]
+        );
+    }
+}
@@ -1,68 +1,176 @@
 use common::{
-    AccountSummary, BalanceByTime, BudgetProgress, BudgetProgressOptions, CategorySpend,
-    CategorySpendOptions, ConfigOptions, DateGrouping, Transaction,
+    AccountSummary, Anomaly, ApiError, BalanceByTime, BalanceOverTime, BalanceTimeOptions,
+    BudgetProgress, BudgetProgressOptions, BulkEditRequest, CategoryComparison, CategorySpend,
+    CategorySpendOptions, ComparisonOptions, ConfigOptions, Flow, FlowOptions, ForecastOptions,
+    Goal, GoalProgress, HoldingValue, KpiSummary, Loan, LoanOptions, LoanStatus, TaxYearOptions,
+    TaxYearReport, Transaction, TransactionPatch,
 };
-use reqwasm::http::Request;
+use reqwasm::http::{Request, Response};
 
-pub async fn get_config(key: &str) -> ConfigOptions {
+pub async fn get_config(key: &str) -> Result<ConfigOptions, ApiError> {
     let query = format!("/api/config/{key}", key = key);
     fetch_data(&query).await
 }
 
-pub async fn get_accounts() -> Vec<AccountSummary> {
+pub async fn get_accounts() -> Result<Vec<AccountSummary>, ApiError> {
     fetch_data("/api/accounts").await
 }
 
-pub async fn balance_by_date(grouping: DateGrouping) -> Vec<BalanceByTime> {
-    fetch_data(&format!("/api/balance?{}", grouping.url_encode())).await
+pub async fn get_anomalies() -> Result<Vec<Anomaly>, ApiError> {
+    fetch_data("/api/insights/anomalies").await
 }
 
-pub async fn get_transactions(offset: usize, limit: usize) -> Vec<Transaction> {
+pub async fn get_goals() -> Result<Vec<GoalProgress>, ApiError> {
+    fetch_data("/api/goals").await
+}
+
+pub async fn get_loans(options: &LoanOptions) -> Result<Vec<LoanStatus>, ApiError> {
+    fetch_data(&format!("/api/loans?{}", options.url_encode())).await
+}
+
+pub async fn get_holdings() -> Result<Vec<HoldingValue>, ApiError> {
+    fetch_data("/api/investments/holdings").await
+}
+
+pub async fn tax_year_report(options: &TaxYearOptions) -> Result<TaxYearReport, ApiError> {
+    fetch_data(&format!("/api/tax?{}", options.url_encode())).await
+}
+
+pub async fn get_kpis() -> Result<KpiSummary, ApiError> {
+    fetch_data("/api/kpis").await
+}
+
+pub async fn balance_by_date(options: &BalanceTimeOptions) -> Result<Vec<BalanceByTime>, ApiError> {
+    fetch_data(&format!("/api/balance?{}", options.url_encode())).await
+}
+
+pub async fn forecast_balance(options: &ForecastOptions) -> Result<BalanceOverTime, ApiError> {
+    fetch_data(&format!("/api/forecast?{}", options.url_encode())).await
+}
+
+pub async fn get_transactions(offset: usize, limit: usize) -> Result<Vec<Transaction>, ApiError> {
     let transaction_endpoint = format!("/api/transactions?offset={offset}&limit={limit}");
     fetch_data(&transaction_endpoint).await
 }
 
-pub async fn budget_progress(options: &BudgetProgressOptions) -> BudgetProgress {
+pub async fn get_trash(offset: usize, limit: usize) -> Result<Vec<Transaction>, ApiError> {
+    fetch_data(&format!("/api/trash?offset={offset}&limit={limit}")).await
+}
+
+pub async fn budget_progress(options: &BudgetProgressOptions) -> Result<BudgetProgress, ApiError> {
     fetch_data(&format!("/api/budget?{}", options.url_encode())).await
 }
 
-pub async fn category_spend(options: &CategorySpendOptions) -> Vec<CategorySpend> {
+pub async fn category_spend(
+    options: &CategorySpendOptions,
+) -> Result<Vec<CategorySpend>, ApiError> {
     fetch_data(&format!("/api/category?{}", options.url_encode())).await
 }
 
-pub async fn create_transaction(transaction: Transaction) {
-    Request::post("/api/transactions")
-        .body(serde_json::to_string(&transaction).unwrap())
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .unwrap();
+pub async fn compare_category_spend(
+    options: &ComparisonOptions,
+) -> Result<Vec<CategoryComparison>, ApiError> {
+    fetch_data(&format!("/api/compare?{}", options.url_encode())).await
 }
 
-pub async fn update_transaction(transaction: Transaction) {
-    Request::patch("/api/transactions")
-        .body(serde_json::to_string(&transaction).unwrap())
-        .header("Content-Type", "application/json")
+pub async fn income_flows(options: &FlowOptions) -> Result<Vec<Flow>, ApiError> {
+    fetch_data(&format!("/api/flows?{}", options.url_encode())).await
+}
+
+pub async fn create_transaction(transaction: Transaction) -> Result<i64, ApiError> {
+    let response = send_data(Request::post("/api/transactions"), &transaction).await?;
+    parse_response(response).await
+}
+
+pub async fn update_transaction(patch: TransactionPatch) -> Result<i64, ApiError> {
+    let response = send_data(Request::patch("/api/transactions"), &patch).await?;
+    parse_response(response).await
+}
+
+pub async fn bulk_update_transactions(request: BulkEditRequest) -> Result<u64, ApiError> {
+    let response = send_data(Request::patch("/api/transactions/bulk"), &request).await?;
+    parse_response(response).await
+}
+
+pub async fn delete_transaction(id: i64) -> Result<(), ApiError> {
+    send_data(Request::delete("/api/transactions"), &id).await?;
+    Ok(())
+}
+
+pub async fn restore_transaction(id: i64) -> Result<(), ApiError> {
+    send_data(Request::patch("/api/trash"), &id).await?;
+    Ok(())
+}
+
+pub async fn purge_transaction(id: i64) -> Result<(), ApiError> {
+    send_data(Request::delete("/api/trash"), &id).await?;
+    Ok(())
+}
+
+pub async fn create_goal(goal: Goal) -> Result<i64, ApiError> {
+    let response = send_data(Request::post("/api/goals"), &goal).await?;
+    parse_response(response).await
+}
+
+pub async fn delete_goal(id: i64) -> Result<(), ApiError> {
+    send_data(Request::delete("/api/goals"), &id).await?;
+    Ok(())
+}
+
+pub async fn create_loan(loan: Loan) -> Result<i64, ApiError> {
+    let response = send_data(Request::post("/api/loans"), &loan).await?;
+    parse_response(response).await
+}
+
+pub async fn delete_loan(id: i64) -> Result<(), ApiError> {
+    send_data(Request::delete("/api/loans"), &id).await?;
+    Ok(())
+}
+
+pub async fn import_prices(csv: String) -> Result<usize, ApiError> {
+    let response = Request::post("/api/investments/prices")
+        .body(csv)
+        .header("Content-Type", "text/csv")
         .send()
         .await
-        .unwrap();
+        .map_err(ApiError::internal)?;
+    let response = check_response(response).await?;
+    parse_response(response).await
+}
+
+async fn fetch_data<T: for<'de> serde::de::Deserialize<'de>>(url: &str) -> Result<T, ApiError> {
+    let response = Request::get(url).send().await.map_err(ApiError::internal)?;
+    let response = check_response(response).await?;
+    parse_response(response).await
 }
 
-pub async fn delete_transaction(id: i64) {
-    Request::delete("/api/transactions")
-        .body(serde_json::to_string(&id).unwrap())
+async fn send_data<B: serde::Serialize>(request: Request, body: &B) -> Result<Response, ApiError> {
+    let body = serde_json::to_string(body).map_err(ApiError::internal)?;
+    let response = request
+        .body(body)
         .header("Content-Type", "application/json")
         .send()
         .await
-        .unwrap();
+        .map_err(ApiError::internal)?;
+    check_response(response).await
 }
 
-async fn fetch_data<T: for<'de> serde::de::Deserialize<'de>>(url: &str) -> T {
-    Request::get(url)
-        .send()
-        .await
-        .unwrap()
-        .json::<T>()
-        .await
-        .unwrap()
+async fn check_response(response: Response) -> Result<Response, ApiError> {
+    if response.ok() {
+        return Ok(response);
+    }
+    match response.json::<ApiError>().await {
+        Ok(e) => Err(e),
+        Err(_) => Err(ApiError::internal(format!(
+            "{} {}",
+            response.status(),
+            response.status_text()
+        ))),
+    }
+}
+
+async fn parse_response<T: for<'de> serde::de::Deserialize<'de>>(
+    response: Response,
+) -> Result<T, ApiError> {
+    response.json::<T>().await.map_err(ApiError::internal)
 }
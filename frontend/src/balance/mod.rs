@@ -1,21 +1,42 @@
 use std::sync::Arc;
 
-use common::{BalanceByTime, BalanceOverTime, BalancesByDayExt, DateGrouping};
-use plotly::{layout::BarMode, Bar, Layout, Plot, Scatter};
+use chrono::NaiveDate;
+use common::{
+    ApiError, BalanceByTime, BalanceOverTime, BalanceTimeOptions, BalancesByDayExt, DateGrouping,
+    ForecastOptions,
+};
+use plotly::{
+    common::{DashType, Line},
+    layout::BarMode,
+    Bar, Layout, Plot, Scatter,
+};
 use yew::prelude::*;
 use yew_plotly::Plotly;
 
-use crate::api;
+use crate::{api, components::ErrorBanner, monthly::DatePicker};
+
+const FORECAST_DAYS: i64 = 90;
 
 pub enum BalanceMsg {
+    Error(ApiError),
     NeedUpdateBalance,
     UpdateBalanceByDay(Vec<BalanceByTime>),
-    UpdateBalanceByMonth(Vec<BalanceByTime>),
+    UpdateBalanceByPeriod(Vec<BalanceByTime>),
+    NeedForecast,
+    UpdateForecast(BalanceOverTime),
+    UpdateFrom(AttrValue),
+    UpdateTo(AttrValue),
+    UpdateGrouping(DateGrouping),
 }
 
 pub struct BalanceComponent {
     balance_by_day: Option<Arc<Vec<BalanceByTime>>>,
-    balance_by_month: Option<Arc<Vec<BalanceByTime>>>,
+    balance_by_period: Option<Arc<Vec<BalanceByTime>>>,
+    forecast: Option<BalanceOverTime>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    grouping: DateGrouping,
+    error: Option<ApiError>,
 }
 
 impl Component for BalanceComponent {
@@ -25,68 +46,173 @@ impl Component for BalanceComponent {
     fn create(ctx: &Context<Self>) -> Self {
         let component = Self {
             balance_by_day: None,
-            balance_by_month: None,
+            balance_by_period: None,
+            forecast: None,
+            from: None,
+            to: None,
+            grouping: DateGrouping::Month,
+            error: None,
         };
 
         ctx.link().send_message(Self::Message::NeedUpdateBalance);
+        ctx.link().send_message(Self::Message::NeedForecast);
 
         component
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
+            BalanceMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
             BalanceMsg::NeedUpdateBalance => {
                 log::info!("Updating balance by day.");
+                /* The running balance needs everything before the range too */
+                let daily = BalanceTimeOptions {
+                    grouping: Some(DateGrouping::Day),
+                    from: None,
+                    to: self.to,
+                };
                 ctx.link().send_future(async move {
-                    BalanceMsg::UpdateBalanceByDay(api::balance_by_date(DateGrouping::Day).await)
+                    match api::balance_by_date(&daily).await {
+                        Ok(balance) => BalanceMsg::UpdateBalanceByDay(balance),
+                        Err(e) => BalanceMsg::Error(e),
+                    }
                 });
+                let period = BalanceTimeOptions {
+                    grouping: Some(self.grouping),
+                    from: self.from,
+                    to: self.to,
+                };
                 ctx.link().send_future(async move {
-                    BalanceMsg::UpdateBalanceByMonth(
-                        api::balance_by_date(DateGrouping::Month).await,
-                    )
+                    match api::balance_by_date(&period).await {
+                        Ok(balance) => BalanceMsg::UpdateBalanceByPeriod(balance),
+                        Err(e) => BalanceMsg::Error(e),
+                    }
                 });
             }
             BalanceMsg::UpdateBalanceByDay(balance_over_time) => {
                 self.balance_by_day = Some(Arc::new(balance_over_time));
             }
-            BalanceMsg::UpdateBalanceByMonth(balance_over_time) => {
-                self.balance_by_month = Some(Arc::new(balance_over_time));
+            BalanceMsg::UpdateBalanceByPeriod(balance_over_time) => {
+                self.balance_by_period = Some(Arc::new(balance_over_time));
+            }
+            BalanceMsg::NeedForecast => {
+                let options = ForecastOptions {
+                    days: FORECAST_DAYS,
+                };
+                ctx.link().send_future(async move {
+                    match api::forecast_balance(&options).await {
+                        Ok(forecast) => BalanceMsg::UpdateForecast(forecast),
+                        Err(e) => BalanceMsg::Error(e),
+                    }
+                });
+            }
+            BalanceMsg::UpdateForecast(forecast) => {
+                self.forecast = Some(forecast);
+            }
+            BalanceMsg::UpdateFrom(d) => {
+                self.from = NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok();
+                ctx.link().send_message(BalanceMsg::NeedUpdateBalance);
+            }
+            BalanceMsg::UpdateTo(d) => {
+                self.to = NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok();
+                ctx.link().send_message(BalanceMsg::NeedUpdateBalance);
+            }
+            BalanceMsg::UpdateGrouping(grouping) => {
+                self.grouping = grouping;
+                ctx.link().send_message(BalanceMsg::NeedUpdateBalance);
             }
         }
         true
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let given_from = self.from.map(|d| d.to_string()).unwrap_or_default();
+        let given_to = self.to.map(|d| d.to_string()).unwrap_or_default();
+        let grouping_html: Html = DateGrouping::ALL
+            .into_iter()
+            .filter(|g| *g != DateGrouping::Day)
+            .map(|g| {
+                html! {
+                    <button disabled={g == self.grouping}
+                    onclick={ctx.link().callback(move |_| BalanceMsg::UpdateGrouping(g))}>
+                    {format!("{g:?}")}
+                    </button>
+                }
+            })
+            .collect();
+
+        let controls = html! {
+            <>
+            <div class="wrapper">
+            <label>{"From "}</label>
+            <DatePicker id="balance_from" given_date={given_from}
+            on_input={ctx.link().callback(BalanceMsg::UpdateFrom)}/>
+            <label>{" To "}</label>
+            <DatePicker id="balance_to" given_date={given_to}
+            on_input={ctx.link().callback(BalanceMsg::UpdateTo)}/>
+            {grouping_html}
+            </div>
+            <ErrorBanner error={self.error.clone()} />
+            </>
+        };
         let Some(balance_by_day) = &self.balance_by_day else {
-            return html! {<></>};
+            return controls;
         };
-        let Some(balance_by_month) = &self.balance_by_month else {
-            return html! {<></>};
+        let Some(balance_by_period) = &self.balance_by_period else {
+            return controls;
         };
 
-        let db = balance_by_day.cumsum();
-        let ma = balance_by_day.rolling_average_cumsum(30);
+        let db = trim_before(balance_by_day.cumsum(), self.from);
+        let ma = balance_by_day
+            .fill_gaps()
+            .rolling_average_cumsum(30)
+            .map(|ma| trim_before(ma, self.from));
 
-        let (months, monthly_incoming, monthly_outgoing, monthly_balance) =
-            balance_by_month.vectors();
+        let (periods, period_incoming, period_outgoing, period_balance) =
+            balance_by_period.vectors();
 
         html! {
             <>
-            <BalanceOverTimeComponent daily_balance={db} ma={ma}/>
-            <BalanceByMonthComponent {months} {monthly_incoming} {monthly_outgoing} {monthly_balance}/>
+            {controls}
+            <BalanceOverTimeComponent daily_balance={db} ma={ma} forecast={self.forecast.clone()}/>
+            <BalanceByPeriodComponent grouping={self.grouping} {periods} {period_incoming}
+            {period_outgoing} {period_balance}/>
             </>
         }
     }
 }
 
+fn trim_before(balance: BalanceOverTime, from: Option<NaiveDate>) -> BalanceOverTime {
+    let Some(from) = from else {
+        return balance;
+    };
+    let from = from.to_string();
+    let start = balance.dates.partition_point(|d| *d < from);
+    BalanceOverTime {
+        dates: balance.dates[start..].to_vec(),
+        balances: balance.balances[start..].to_vec(),
+    }
+}
+
 #[derive(Properties, PartialEq)]
 pub struct BalancePlotProps {
     pub daily_balance: BalanceOverTime,
     pub ma: Option<BalanceOverTime>,
+    #[prop_or_default]
+    pub forecast: Option<BalanceOverTime>,
 }
 
 #[function_component(BalanceOverTimeComponent)]
-pub fn balance_component(BalancePlotProps { daily_balance, ma }: &BalancePlotProps) -> Html {
+pub fn balance_component(
+    BalancePlotProps {
+        daily_balance,
+        ma,
+        forecast,
+    }: &BalancePlotProps,
+) -> Html {
     let mut plot = Plot::new();
     let trace = Scatter::new(
         daily_balance.dates.to_owned(),
@@ -105,6 +231,13 @@ pub fn balance_component(BalancePlotProps { daily_balance, ma }: &BalancePlotPro
         None => (),
     };
 
+    if let Some(forecast) = forecast {
+        let forecast_trace = Scatter::new(forecast.dates.to_owned(), forecast.balances.to_owned())
+            .name("Projected")
+            .line(Line::new().dash(DashType::Dash));
+        plot.add_trace(forecast_trace);
+    }
+
     let layout = Layout::new().title("Daily Total Balance".into());
 
     plot.set_layout(layout);
@@ -114,35 +247,37 @@ pub fn balance_component(BalancePlotProps { daily_balance, ma }: &BalancePlotPro
 
 #[derive(Properties, PartialEq)]
 pub struct BalanceBarPlotProps {
-    pub months: Vec<String>,
-    pub monthly_incoming: Vec<f64>,
-    pub monthly_outgoing: Vec<f64>,
-    pub monthly_balance: Vec<f64>,
+    pub grouping: DateGrouping,
+    pub periods: Vec<String>,
+    pub period_incoming: Vec<f64>,
+    pub period_outgoing: Vec<f64>,
+    pub period_balance: Vec<f64>,
 }
 
-#[function_component(BalanceByMonthComponent)]
-pub fn balance_by_month_component(
+#[function_component(BalanceByPeriodComponent)]
+pub fn balance_by_period_component(
     BalanceBarPlotProps {
-        months,
-        monthly_outgoing,
-        monthly_incoming,
-        monthly_balance,
+        grouping,
+        periods,
+        period_outgoing,
+        period_incoming,
+        period_balance,
     }: &BalanceBarPlotProps,
 ) -> Html {
     let mut plot = Plot::new();
 
-    let out_trace = Bar::new(months.to_owned(), monthly_outgoing.to_owned()).name("Outgoing");
+    let out_trace = Bar::new(periods.to_owned(), period_outgoing.to_owned()).name("Outgoing");
     plot.add_trace(out_trace);
 
-    let in_trace = Bar::new(months.to_owned(), monthly_incoming.to_owned()).name("Incoming");
+    let in_trace = Bar::new(periods.to_owned(), period_incoming.to_owned()).name("Incoming");
     plot.add_trace(in_trace);
 
-    let total_trace = Scatter::new(months.to_owned(), monthly_balance.to_owned()).name("Total");
+    let total_trace = Scatter::new(periods.to_owned(), period_balance.to_owned()).name("Total");
     plot.add_trace(total_trace);
 
     let layout = Layout::new()
         .bar_mode(BarMode::Overlay)
-        .title("Monthly Incoming/Outgoing".into());
+        .title(format!("Incoming/Outgoing by {grouping:?}").as_str().into());
 
     plot.set_layout(layout);
 
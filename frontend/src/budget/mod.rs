@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
-use chrono::{Datelike, NaiveDate, Utc};
+use chrono::Utc;
 use common::{
-    BudgetProgress, BudgetProgressOptions, CategorySpend, CategorySpendOptions, ConfigOptions,
+    ApiError, BudgetProgress, BudgetProgressOptions, CategorySpend, CategorySpendOptions,
+    ConfigOptions, PeriodStart, TagLevel,
 };
 use plotly::{
     color::NamedColor,
@@ -13,21 +14,29 @@ use plotly::{
 use yew::prelude::*;
 use yew_plotly::Plotly;
 
-use crate::{api, components::CategorySpendComponent};
+use crate::{
+    api,
+    components::{CategorySpendComponent, ErrorBanner},
+};
 
 pub enum BudgetMsg {
+    Error(ApiError),
     NeedProgressData,
     UpdateProgressData(BudgetProgress),
     NeedCategorySpend,
     UpdateCategorySpend(Vec<CategorySpend>),
     NeedCategories,
     UpdateCategories(ConfigOptions),
+    NeedPeriodStart,
+    UpdatePeriodStart(ConfigOptions),
 }
 
 pub struct BudgetComponent {
     budget_progress: Option<BudgetProgress>,
     category_spend: Option<Arc<Vec<CategorySpend>>>,
     categories: Option<Arc<Vec<String>>>,
+    period_start: Option<PeriodStart>,
+    error: Option<ApiError>,
 }
 
 impl Component for BudgetComponent {
@@ -37,32 +46,59 @@ impl Component for BudgetComponent {
     fn create(ctx: &Context<Self>) -> Self {
         ctx.link().send_message(Self::Message::NeedCategories);
         ctx.link().send_message(Self::Message::NeedProgressData);
+        ctx.link().send_message(Self::Message::NeedPeriodStart);
 
         Self {
             budget_progress: None,
             category_spend: None,
             categories: None,
+            period_start: None,
+            error: None,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
+            BudgetMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
             BudgetMsg::NeedCategories => ctx.link().send_future(async move {
-                BudgetMsg::UpdateCategories(api::get_config("budget_items").await)
+                match api::get_config("budget_items").await {
+                    Ok(config) => BudgetMsg::UpdateCategories(config),
+                    Err(e) => BudgetMsg::Error(e),
+                }
             }),
             BudgetMsg::UpdateCategories(config) => match config {
                 ConfigOptions::BudgetItems(c) => {
                     self.categories = Some(Arc::new(c));
                     ctx.link().send_message(Self::Message::NeedCategorySpend);
                 }
-                _ => panic!("wrong config option variant"),
+                _ => ctx.link().send_message(BudgetMsg::Error(ApiError::internal(
+                    "wrong config option variant",
+                ))),
+            },
+            BudgetMsg::NeedPeriodStart => ctx.link().send_future(async move {
+                match api::get_config("period_start").await {
+                    Ok(config) => BudgetMsg::UpdatePeriodStart(config),
+                    Err(e) => BudgetMsg::Error(e),
+                }
+            }),
+            BudgetMsg::UpdatePeriodStart(config) => match config {
+                ConfigOptions::PeriodStart(p) => self.period_start = Some(p),
+                _ => ctx.link().send_message(BudgetMsg::Error(ApiError::internal(
+                    "wrong config option variant",
+                ))),
             },
             BudgetMsg::NeedProgressData => {
                 let options = BudgetProgressOptions {
                     date: Utc::now().date_naive(),
                 };
                 ctx.link().send_future(async move {
-                    BudgetMsg::UpdateProgressData(api::budget_progress(&options).await)
+                    match api::budget_progress(&options).await {
+                        Ok(progress) => BudgetMsg::UpdateProgressData(progress),
+                        Err(e) => BudgetMsg::Error(e),
+                    }
                 });
             }
             BudgetMsg::UpdateProgressData(spend) => {
@@ -76,9 +112,14 @@ impl Component for BudgetComponent {
                 let options = CategorySpendOptions {
                     date: Utc::now().date_naive(),
                     l1_tags: categories.clone().to_vec(),
+                    level: TagLevel::L1,
+                    parent: vec![],
                 };
                 ctx.link().send_future(async move {
-                    BudgetMsg::UpdateCategorySpend(api::category_spend(&options).await)
+                    match api::category_spend(&options).await {
+                        Ok(spend) => BudgetMsg::UpdateCategorySpend(spend),
+                        Err(e) => BudgetMsg::Error(e),
+                    }
                 });
             }
             BudgetMsg::UpdateCategorySpend(category) => {
@@ -89,12 +130,17 @@ impl Component for BudgetComponent {
     }
 
     fn view(&self, _ctx: &Context<Self>) -> Html {
+        let error = html! { <ErrorBanner error={self.error.clone()} /> };
         let Some(budget_progress) = &self.budget_progress else {
-            return html! {<></>};
+            return error;
         };
 
         let Some(category_spend) = &self.category_spend else {
-            return html! {<></>};
+            return error;
+        };
+
+        let Some(period_start) = self.period_start else {
+            return error;
         };
         let progress = budget_progress.progress() * 100.0;
         let progress = format!("{:.2}%", progress);
@@ -103,11 +149,12 @@ impl Component for BudgetComponent {
         let spent = format!("£{:.2}", spent);
 
         let today = Utc::now().date_naive();
-        let expected = progress_through_month(today) * 100.0;
+        let expected = period_start.progress(today) * 100.0;
         let expected = format!("{:.2}%", expected);
 
         html! {
         <>
+        {error}
         <div class="row">
             <div class="wrapper">
                 <div class="info"><h2>{"Spent "} {spent}</h2></div>
@@ -132,25 +179,6 @@ impl Component for BudgetComponent {
     }
 }
 
-fn progress_through_month(date: NaiveDate) -> f64 {
-    let start_month = NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap();
-    let next_month = NaiveDate::from_ymd_opt(
-        match date.month() {
-            12 => date.year() + 1,
-            _ => date.year(),
-        },
-        match date.month() {
-            12 => 1,
-            _ => date.month() + 1,
-        },
-        1,
-    )
-    .unwrap();
-    let number_of_days = next_month.signed_duration_since(start_month).num_days();
-
-    date.day() as f64 / number_of_days as f64
-}
-
 #[derive(Properties, PartialEq)]
 pub struct BudgetBarPlotProps {
     budget_progress: BudgetProgress,
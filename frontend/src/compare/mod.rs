@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use common::{ApiError, CategoryComparison, ComparisonOptions, ConfigOptions};
+use yew::prelude::*;
+
+use crate::{
+    api,
+    components::{CategoryComparisonComponent, ErrorBanner},
+    monthly::DatePicker,
+};
+
+pub enum CompareMsg {
+    Error(ApiError),
+    NeedCategories,
+    UpdateCategories(ConfigOptions),
+    NeedComparison,
+    UpdateComparison(Vec<CategoryComparison>),
+    UpdateFirstFrom(AttrValue),
+    UpdateFirstTo(AttrValue),
+    UpdateSecondFrom(AttrValue),
+    UpdateSecondTo(AttrValue),
+    MonthOverMonth,
+    YearOverYear,
+}
+
+pub struct CompareComponent {
+    first: (NaiveDate, NaiveDate),
+    second: (NaiveDate, NaiveDate),
+    categories: Option<Arc<Vec<String>>>,
+    comparison: Option<Arc<Vec<CategoryComparison>>>,
+    error: Option<ApiError>,
+}
+
+impl Component for CompareComponent {
+    type Message = CompareMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedCategories);
+
+        let second = month_to_date(Utc::now().date_naive());
+        Self {
+            first: shift_back(second, Months::new(1)),
+            second,
+            categories: None,
+            comparison: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            CompareMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            CompareMsg::NeedCategories => ctx.link().send_future(async move {
+                match api::get_config("period_items").await {
+                    Ok(config) => CompareMsg::UpdateCategories(config),
+                    Err(e) => CompareMsg::Error(e),
+                }
+            }),
+            CompareMsg::UpdateCategories(config) => match config {
+                ConfigOptions::PeriodItems(c) => {
+                    self.categories = Some(Arc::new(c));
+                    ctx.link().send_message(Self::Message::NeedComparison);
+                }
+                _ => ctx
+                    .link()
+                    .send_message(CompareMsg::Error(ApiError::internal(
+                        "wrong config option variant",
+                    ))),
+            },
+            CompareMsg::NeedComparison => {
+                let Some(categories) = &self.categories else {
+                    ctx.link().send_message(CompareMsg::NeedCategories);
+                    return false;
+                };
+                let options = ComparisonOptions {
+                    first_from: self.first.0,
+                    first_to: self.first.1,
+                    second_from: self.second.0,
+                    second_to: self.second.1,
+                    l1_tags: categories.to_vec(),
+                };
+                ctx.link().send_future(async move {
+                    match api::compare_category_spend(&options).await {
+                        Ok(comparison) => CompareMsg::UpdateComparison(comparison),
+                        Err(e) => CompareMsg::Error(e),
+                    }
+                });
+            }
+            CompareMsg::UpdateComparison(comparison) => {
+                self.error = None;
+                self.comparison = Some(Arc::new(comparison));
+            }
+            CompareMsg::UpdateFirstFrom(d) => update_date(ctx, &mut self.first.0, &d),
+            CompareMsg::UpdateFirstTo(d) => update_date(ctx, &mut self.first.1, &d),
+            CompareMsg::UpdateSecondFrom(d) => update_date(ctx, &mut self.second.0, &d),
+            CompareMsg::UpdateSecondTo(d) => update_date(ctx, &mut self.second.1, &d),
+            CompareMsg::MonthOverMonth => {
+                self.first = shift_back(self.second, Months::new(1));
+                ctx.link().send_message(CompareMsg::NeedComparison);
+            }
+            CompareMsg::YearOverYear => {
+                self.first = shift_back(self.second, Months::new(12));
+                ctx.link().send_message(CompareMsg::NeedComparison);
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let first_label = format!("{} to {}", self.first.0, self.first.1);
+        let second_label = format!("{} to {}", self.second.0, self.second.1);
+
+        let controls = html! {
+            <>
+            <div class="wrapper">
+            <label>{"First "}</label>
+            <DatePicker id="first_from" given_date={self.first.0.to_string()}
+            on_input={ctx.link().callback(CompareMsg::UpdateFirstFrom)}/>
+            <DatePicker id="first_to" given_date={self.first.1.to_string()}
+            on_input={ctx.link().callback(CompareMsg::UpdateFirstTo)}/>
+            <label>{" Second "}</label>
+            <DatePicker id="second_from" given_date={self.second.0.to_string()}
+            on_input={ctx.link().callback(CompareMsg::UpdateSecondFrom)}/>
+            <DatePicker id="second_to" given_date={self.second.1.to_string()}
+            on_input={ctx.link().callback(CompareMsg::UpdateSecondTo)}/>
+            <button onclick={ctx.link().callback(|_| CompareMsg::MonthOverMonth)}>{"Month over month"}</button>
+            <button onclick={ctx.link().callback(|_| CompareMsg::YearOverYear)}>{"Year over year"}</button>
+            </div>
+            <ErrorBanner error={self.error.clone()} />
+            </>
+        };
+        let Some(comparison) = &self.comparison else {
+            return controls;
+        };
+
+        let rows: Html = comparison
+            .iter()
+            .map(|c| {
+                let percentage = c
+                    .percentage_change
+                    .map_or_else(|| "-".to_string(), |p| format!("{p:+.1}%"));
+                html! {
+                    <tr>
+                        <td>{c.name.clone()}</td>
+                        <td>{format!("£{:.2}", c.first)}</td>
+                        <td>{format!("£{:.2}", c.second)}</td>
+                        <td>{format!("£{:+.2}", c.change)}</td>
+                        <td>{percentage}</td>
+                    </tr>
+                }
+            })
+            .collect();
+
+        html! {
+            <>
+            {controls}
+            <div class="row">
+            <div id="chart" class="chart">
+                <CategoryComparisonComponent comparison={comparison.clone()}
+                first_label={first_label.clone()} second_label={second_label.clone()}/>
+            </div>
+            </div>
+            <table class="data">
+            <tr>
+                <th>{"Category"}</th>
+                <th>{first_label}</th>
+                <th>{second_label}</th>
+                <th>{"Change"}</th>
+                <th>{"Change %"}</th>
+            </tr>
+            {rows}
+            </table>
+            </>
+        }
+    }
+}
+
+fn update_date(ctx: &Context<CompareComponent>, bound: &mut NaiveDate, date: &str) {
+    if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        *bound = date;
+        ctx.link().send_message(CompareMsg::NeedComparison);
+    }
+}
+
+fn month_to_date(today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    (today.with_day(1).unwrap_or(today), today)
+}
+
+fn shift_back((from, to): (NaiveDate, NaiveDate), months: Months) -> (NaiveDate, NaiveDate) {
+    (
+        from.checked_sub_months(months).unwrap_or(from),
+        to.checked_sub_months(months).unwrap_or(to),
+    )
+}
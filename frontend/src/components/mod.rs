@@ -1,18 +1,23 @@
 use std::sync::Arc;
 
-use common::CategorySpend;
-use plotly::{Bar, Layout, Plot};
+use common::{ApiError, CategoryComparison, CategorySpend};
+use plotly::{layout::BarMode, Bar, Layout, Plot};
 use yew::prelude::*;
 use yew_plotly::Plotly;
 
 #[derive(Properties, PartialEq)]
 pub struct CategorySpendProps {
     pub category_spend: Arc<Vec<CategorySpend>>,
+    #[prop_or_default]
+    pub on_select: Option<Callback<String>>,
 }
 
 #[function_component(CategorySpendComponent)]
 pub fn category_spend_component(
-    CategorySpendProps { category_spend }: &CategorySpendProps,
+    CategorySpendProps {
+        category_spend,
+        on_select,
+    }: &CategorySpendProps,
 ) -> Html {
     let mut categories = vec![];
     let mut spend = vec![];
@@ -30,5 +35,81 @@ pub fn category_spend_component(
 
     plot.set_layout(layout);
 
+    /* yew-plotly doesn't pass plot click events through, so each bar gets a button */
+    let bar_buttons: Html = match on_select {
+        Some(on_select) => category_spend
+            .iter()
+            .map(|c| {
+                let name = c.name.clone();
+                html! {
+                    <button onclick={on_select.reform(move |_| name.clone())}>
+                    {format!("{} £{:.2}", c.name, c.amount.unwrap_or(0.0))}
+                    </button>
+                }
+            })
+            .collect(),
+        None => html! {<></>},
+    };
+
+    html! {
+        <>
+        <Plotly plot={plot}/>
+        <div class="wrapper">{bar_buttons}</div>
+        </>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CategoryComparisonProps {
+    pub comparison: Arc<Vec<CategoryComparison>>,
+    pub first_label: AttrValue,
+    pub second_label: AttrValue,
+}
+
+#[function_component(CategoryComparisonComponent)]
+pub fn category_comparison_component(
+    CategoryComparisonProps {
+        comparison,
+        first_label,
+        second_label,
+    }: &CategoryComparisonProps,
+) -> Html {
+    let mut categories = vec![];
+    let mut first = vec![];
+    let mut second = vec![];
+    for c in comparison.iter() {
+        categories.push(c.name.clone());
+        first.push(c.first);
+        second.push(c.second);
+    }
+
+    let mut plot = Plot::new();
+    plot.add_trace(Bar::new(categories.clone(), first).name(first_label.as_str()));
+    plot.add_trace(Bar::new(categories, second).name(second_label.as_str()));
+
+    let layout = Layout::new()
+        .bar_mode(BarMode::Group)
+        .title("Category Spend Comparison".into());
+
+    plot.set_layout(layout);
+
     html! { <Plotly plot={plot}/> }
 }
+
+#[derive(Properties, PartialEq)]
+pub struct ErrorBannerProps {
+    pub error: Option<ApiError>,
+}
+
+#[function_component(ErrorBanner)]
+pub fn error_banner(ErrorBannerProps { error }: &ErrorBannerProps) -> Html {
+    let Some(error) = error else {
+        return html! {<></>};
+    };
+
+    html! {
+        <div class="error">
+            <p>{error.message.clone()}</p>
+        </div>
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use common::{ApiError, Flow, FlowOptions};
+use plotly::{
+    sankey::{Link, Node},
+    Layout, Plot, Sankey,
+};
+use yew::prelude::*;
+use yew_plotly::Plotly;
+
+use crate::{api, components::ErrorBanner, monthly::DatePicker};
+
+pub enum FlowsMsg {
+    Error(ApiError),
+    NeedFlows,
+    UpdateFlows(Vec<Flow>),
+    UpdateFrom(AttrValue),
+    UpdateTo(AttrValue),
+}
+
+pub struct FlowsComponent {
+    from: NaiveDate,
+    to: NaiveDate,
+    flows: Option<Arc<Vec<Flow>>>,
+    error: Option<ApiError>,
+}
+
+impl Component for FlowsComponent {
+    type Message = FlowsMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedFlows);
+
+        let today = Utc::now().date_naive();
+        Self {
+            from: today.with_day(1).unwrap_or(today),
+            to: today,
+            flows: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            FlowsMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            FlowsMsg::NeedFlows => {
+                let options = FlowOptions {
+                    from: self.from,
+                    to: self.to,
+                };
+                ctx.link().send_future(async move {
+                    match api::income_flows(&options).await {
+                        Ok(flows) => FlowsMsg::UpdateFlows(flows),
+                        Err(e) => FlowsMsg::Error(e),
+                    }
+                });
+            }
+            FlowsMsg::UpdateFlows(flows) => {
+                self.error = None;
+                self.flows = Some(Arc::new(flows));
+            }
+            FlowsMsg::UpdateFrom(d) => {
+                if let Ok(d) = NaiveDate::parse_from_str(&d, "%Y-%m-%d") {
+                    self.from = d;
+                    ctx.link().send_message(FlowsMsg::NeedFlows);
+                }
+            }
+            FlowsMsg::UpdateTo(d) => {
+                if let Ok(d) = NaiveDate::parse_from_str(&d, "%Y-%m-%d") {
+                    self.to = d;
+                    ctx.link().send_message(FlowsMsg::NeedFlows);
+                }
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let controls = html! {
+            <>
+            <div class="wrapper">
+            <label>{"From "}</label>
+            <DatePicker id="flows_from" given_date={self.from.to_string()}
+            on_input={ctx.link().callback(FlowsMsg::UpdateFrom)}/>
+            <label>{" To "}</label>
+            <DatePicker id="flows_to" given_date={self.to.to_string()}
+            on_input={ctx.link().callback(FlowsMsg::UpdateTo)}/>
+            </div>
+            <ErrorBanner error={self.error.clone()} />
+            </>
+        };
+        let Some(flows) = &self.flows else {
+            return controls;
+        };
+
+        html! {
+            <>
+            {controls}
+            <div class="row">
+            <div id="chart" class="chart">
+                <FlowsChartComponent flows={flows.clone()} />
+            </div>
+            </div>
+            </>
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct FlowsChartProps {
+    pub flows: Arc<Vec<Flow>>,
+}
+
+#[function_component(FlowsChartComponent)]
+pub fn flows_chart_component(FlowsChartProps { flows }: &FlowsChartProps) -> Html {
+    let mut labels: Vec<&str> = vec![];
+    let mut sources = vec![];
+    let mut targets = vec![];
+    let mut values = vec![];
+    for flow in flows.iter() {
+        sources.push(node_index(&mut labels, &flow.source));
+        targets.push(node_index(&mut labels, &flow.target));
+        values.push(flow.amount);
+    }
+
+    let mut plot = Plot::new();
+    let trace = Sankey::new()
+        .node(Node::new().label(labels).pad(15).thickness(20))
+        .link(Link::new().source(sources).target(targets).value(values));
+    plot.add_trace(trace);
+
+    let layout = Layout::new().title("Where The Income Goes".into());
+
+    plot.set_layout(layout);
+
+    html! { <Plotly plot={plot}/> }
+}
+
+fn node_index<'a>(labels: &mut Vec<&'a str>, name: &'a str) -> usize {
+    labels.iter().position(|l| *l == name).unwrap_or_else(|| {
+        labels.push(name);
+        labels.len() - 1
+    })
+}
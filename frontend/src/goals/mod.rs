@@ -0,0 +1,275 @@
+use chrono::{Months, NaiveDate, Utc};
+use common::{ApiError, ConfigOptions, FieldError, Goal, GoalLink, GoalProgress};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{
+    api,
+    components::ErrorBanner,
+    home::fields::{AccountPicker, FieldErrorMessage},
+    monthly::DatePicker,
+};
+
+pub enum GoalsMsg {
+    Error(ApiError),
+    NeedGoals,
+    UpdateGoals(Vec<GoalProgress>),
+    NeedAccounts,
+    UpdateAccounts(ConfigOptions),
+    UpdateName(AttrValue),
+    UpdateTarget(AttrValue),
+    UpdateTargetDate(AttrValue),
+    UpdateLinkKind(AttrValue),
+    UpdateAccount(AttrValue),
+    UpdateTag(AttrValue),
+    Submit,
+    Delete(i64),
+}
+
+pub struct GoalsComponent {
+    goals: Vec<GoalProgress>,
+    accounts: Vec<String>,
+    name: AttrValue,
+    target: AttrValue,
+    target_date: AttrValue,
+    link_kind: AttrValue,
+    account: AttrValue,
+    tag: AttrValue,
+    error: Option<ApiError>,
+}
+
+impl Component for GoalsComponent {
+    type Message = GoalsMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedGoals);
+        ctx.link().send_message(Self::Message::NeedAccounts);
+
+        let today = Utc::now().date_naive();
+        let target_date = today.checked_add_months(Months::new(12)).unwrap_or(today);
+        Self {
+            goals: vec![],
+            accounts: vec![],
+            name: AttrValue::default(),
+            target: AttrValue::default(),
+            target_date: AttrValue::from(target_date.to_string()),
+            link_kind: AttrValue::from("Account"),
+            account: AttrValue::default(),
+            tag: AttrValue::default(),
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            GoalsMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            GoalsMsg::NeedGoals => ctx.link().send_future(async move {
+                match api::get_goals().await {
+                    Ok(goals) => GoalsMsg::UpdateGoals(goals),
+                    Err(e) => GoalsMsg::Error(e),
+                }
+            }),
+            GoalsMsg::UpdateGoals(goals) => self.goals = goals,
+            GoalsMsg::NeedAccounts => ctx.link().send_future(async move {
+                match api::get_config("account_list").await {
+                    Ok(config) => GoalsMsg::UpdateAccounts(config),
+                    Err(e) => GoalsMsg::Error(e),
+                }
+            }),
+            GoalsMsg::UpdateAccounts(config) => match config {
+                ConfigOptions::AccountList(accounts) => {
+                    if self.account.is_empty() {
+                        self.account = accounts.first().cloned().unwrap_or_default().into();
+                    }
+                    self.accounts = accounts;
+                }
+                _ => ctx.link().send_message(GoalsMsg::Error(ApiError::internal(
+                    "wrong config option variant",
+                ))),
+            },
+            GoalsMsg::UpdateName(name) => self.name = name,
+            GoalsMsg::UpdateTarget(target) => self.target = target,
+            GoalsMsg::UpdateTargetDate(date) => self.target_date = date,
+            GoalsMsg::UpdateLinkKind(kind) => self.link_kind = kind,
+            GoalsMsg::UpdateAccount(account) => self.account = account,
+            GoalsMsg::UpdateTag(tag) => self.tag = tag,
+            GoalsMsg::Submit => {
+                let goal = match self.to_goal() {
+                    Ok(goal) => goal,
+                    Err(e) => {
+                        ctx.link().send_message(GoalsMsg::Error(e));
+                        return false;
+                    }
+                };
+                ctx.link().send_future(async move {
+                    match api::create_goal(goal).await {
+                        Ok(_) => GoalsMsg::NeedGoals,
+                        Err(e) => GoalsMsg::Error(e),
+                    }
+                });
+                self.error = None;
+                self.name = AttrValue::default();
+                self.target = AttrValue::default();
+            }
+            GoalsMsg::Delete(id) => ctx.link().send_future(async move {
+                match api::delete_goal(id).await {
+                    Ok(_) => GoalsMsg::NeedGoals,
+                    Err(e) => GoalsMsg::Error(e),
+                }
+            }),
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let rows: Html = self
+            .goals
+            .iter()
+            .map(|g| {
+                let id = g.goal.id;
+                let linked = match &g.goal.link {
+                    GoalLink::Account(account) => account.clone(),
+                    GoalLink::Tag(tag) => format!("Tag: {tag}"),
+                };
+                html! {
+                    <tr>
+                        <td>{g.goal.name.clone()}</td>
+                        <td>{linked}</td>
+                        <td><progress value={g.progress().to_string()} max="1"></progress></td>
+                        <td>{format!("£{:.2} of £{:.2}", g.saved, g.goal.target)}</td>
+                        <td>{g.goal.target_date.to_string()}</td>
+                        <td>{format!("£{:.2}", g.monthly_contribution)}</td>
+                        <td>
+                        <button onclick={ctx.link().callback(move |_| GoalsMsg::Delete(id))}>
+                        {"Delete"}
+                        </button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect();
+
+        let link_field = if self.link_kind == "Tag" {
+            html! {
+                <input class="form-control" id="tag" value={self.tag.clone()}
+                oninput={ctx.link().callback(|e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    GoalsMsg::UpdateTag(AttrValue::from(input.value()))
+                })}/>
+            }
+        } else {
+            html! {
+                <AccountPicker id="goal" account_list={self.accounts.clone()}
+                given_account={self.account.clone()}
+                on_input={ctx.link().callback(GoalsMsg::UpdateAccount)}/>
+            }
+        };
+
+        html! {
+            <>
+            <ErrorBanner error={self.error.clone()} />
+            <table class="data">
+            <tr>
+                <th>{"Goal"}</th>
+                <th>{"Saving into"}</th>
+                <th>{"Progress"}</th>
+                <th>{"Saved"}</th>
+                <th>{"Target date"}</th>
+                <th>{"Monthly needed"}</th>
+                <th></th>
+            </tr>
+            {rows}
+            </table>
+            <table class="input_tran">
+            <tr>
+                <th><label for="name">{"Name"}</label></th>
+                <th><label for="target">{"Target"}</label></th>
+                <th><label for="goal_date">{"Target date"}</label></th>
+                <th><label for="link_kind">{"Track"}</label></th>
+                <th></th>
+            </tr>
+            <tr>
+                <td>
+                <input class="form-control" id="name" value={self.name.clone()}
+                oninput={ctx.link().callback(|e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    GoalsMsg::UpdateName(AttrValue::from(input.value()))
+                })}/>
+                <FieldErrorMessage error={self.error.clone()} field="name"/>
+                </td>
+                <td>
+                <input class="form-control" id="target" value={self.target.clone()}
+                oninput={ctx.link().callback(|e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    GoalsMsg::UpdateTarget(AttrValue::from(input.value()))
+                })}/>
+                <FieldErrorMessage error={self.error.clone()} field="target"/>
+                </td>
+                <td>
+                <DatePicker id="goal_date" given_date={self.target_date.clone()}
+                on_input={ctx.link().callback(GoalsMsg::UpdateTargetDate)}/>
+                <FieldErrorMessage error={self.error.clone()} field="target_date"/>
+                </td>
+                <td>
+                <select class="form-control" id="link_kind"
+                oninput={ctx.link().callback(|e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    GoalsMsg::UpdateLinkKind(AttrValue::from(input.value()))
+                })}>
+                    <option selected={self.link_kind == "Account"}>{"Account"}</option>
+                    <option selected={self.link_kind == "Tag"}>{"Tag"}</option>
+                </select>
+                </td>
+                <td>
+                {link_field}
+                <FieldErrorMessage error={self.error.clone()} field="account"/>
+                <FieldErrorMessage error={self.error.clone()} field="tag"/>
+                </td>
+            </tr>
+            </table>
+            <button onclick={ctx.link().callback(|_| GoalsMsg::Submit)}>{"Add goal"}</button>
+            </>
+        }
+    }
+}
+
+impl GoalsComponent {
+    fn to_goal(&self) -> Result<Goal, ApiError> {
+        let mut field_errors = vec![];
+
+        let target = self.target.parse::<f64>().unwrap_or_else(|_| {
+            field_errors.push(FieldError::new(
+                "target",
+                format!("Bad amount {:?}.", &self.target),
+            ));
+            0.0
+        });
+        let target_date =
+            NaiveDate::parse_from_str(&self.target_date, "%Y-%m-%d").unwrap_or_else(|_| {
+                field_errors.push(FieldError::new(
+                    "target_date",
+                    format!("Bad date {:?}.", &self.target_date),
+                ));
+                NaiveDate::default()
+            });
+        let link = match self.link_kind.as_str() {
+            "Tag" => GoalLink::Tag(self.tag.to_string()),
+            _ => GoalLink::Account(self.account.to_string()),
+        };
+
+        if !field_errors.is_empty() {
+            return Err(ApiError::validation(field_errors));
+        }
+        Ok(Goal {
+            id: 0,
+            name: self.name.to_string(),
+            target,
+            target_date,
+            link,
+        })
+    }
+}
@@ -0,0 +1,209 @@
+use std::{collections::HashMap, iter, sync::Arc};
+
+use common::{ApiError, BulkEditRequest, BulkSelection, BulkUpdate, Config, FieldError};
+use yew::prelude::*;
+
+use super::fields;
+use crate::api;
+
+pub enum BulkEditMsg {
+    Error(ApiError),
+    Submit,
+    Success,
+    Clear,
+    UpdateAccount(AttrValue),
+    UpdateDescriptionPrefix(AttrValue),
+    UpdateDateShift(AttrValue),
+    UpdateTags((AttrValue, AttrValue, AttrValue)),
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct BulkEditProps {
+    pub ids: Vec<i64>,
+    pub on_submit: Callback<()>,
+    pub on_clear: Callback<()>,
+    pub on_error: Callback<ApiError>,
+    pub config: Arc<Config>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UserBulkUpdate {
+    pub account: AttrValue,
+    pub description_prefix: AttrValue,
+    pub date_shift: AttrValue,
+    pub l1_tag: AttrValue,
+    pub l2_tag: AttrValue,
+    pub l3_tag: AttrValue,
+}
+
+impl UserBulkUpdate {
+    pub fn to_update(&self, config: &Config) -> Result<BulkUpdate, ApiError> {
+        let non_empty = |v: &AttrValue| (!v.is_empty()).then(|| v.to_string());
+
+        let tags = non_empty(&self.l1_tag)
+            .map(|l1_tag| (l1_tag, self.l2_tag.to_string(), self.l3_tag.to_string()));
+
+        let date_shift = match non_empty(&self.date_shift).map(|d| d.parse::<i64>()) {
+            None => None,
+            Some(Ok(d)) => Some(d),
+            Some(Err(_)) => {
+                return Err(ApiError::validation(vec![FieldError::new(
+                    "date_shift",
+                    format!("Bad date shift {:?}.", &self.date_shift),
+                )]))
+            }
+        };
+
+        let update = BulkUpdate {
+            account: non_empty(&self.account),
+            tags,
+            description_prefix: non_empty(&self.description_prefix),
+            date_shift,
+        };
+        if update.is_empty() {
+            return Err(ApiError::bad_request("Nothing to update."));
+        }
+        config.validate_bulk_update(&update)?;
+        Ok(update)
+    }
+}
+
+pub struct BulkEditBar {
+    update: UserBulkUpdate,
+    error: Option<ApiError>,
+}
+
+impl Component for BulkEditBar {
+    type Message = BulkEditMsg;
+    type Properties = BulkEditProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            update: UserBulkUpdate::default(),
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            BulkEditMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e.clone());
+                ctx.props().on_error.emit(e);
+            }
+            BulkEditMsg::Success => {
+                log::info!("Applied bulk update");
+                self.error = None;
+                self.update = UserBulkUpdate::default();
+                ctx.props().on_submit.emit(());
+            }
+            BulkEditMsg::Submit => {
+                let update = match self.update.to_update(&ctx.props().config) {
+                    Ok(u) => u,
+                    Err(e) => {
+                        log::info!("Failed conversion: {e}");
+                        ctx.link().send_message(BulkEditMsg::Error(e));
+                        return false;
+                    }
+                };
+                let request = BulkEditRequest {
+                    selection: BulkSelection::Ids(ctx.props().ids.clone()),
+                    update,
+                };
+                log::info!("Making API bulk patch with {:?}.", request);
+                ctx.link().send_future(async move {
+                    match api::bulk_update_transactions(request).await {
+                        Ok(_) => BulkEditMsg::Success,
+                        Err(e) => BulkEditMsg::Error(e),
+                    }
+                });
+            }
+            BulkEditMsg::Clear => {
+                self.update = UserBulkUpdate::default();
+                self.error = None;
+                ctx.props().on_clear.emit(());
+            }
+            BulkEditMsg::UpdateAccount(account) => {
+                self.update.account = account;
+            }
+            BulkEditMsg::UpdateDescriptionPrefix(prefix) => {
+                self.update.description_prefix = prefix;
+            }
+            BulkEditMsg::UpdateDateShift(shift) => {
+                self.update.date_shift = shift;
+            }
+            BulkEditMsg::UpdateTags(tags) => {
+                self.update.l1_tag = tags.0;
+                self.update.l2_tag = tags.1;
+                self.update.l3_tag = tags.2;
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let id = "bulk".to_string();
+        let config = &ctx.props().config;
+
+        /* A blank choice means "leave unchanged" */
+        let account_list: Vec<String> = iter::once(String::new())
+            .chain(config.account_list().iter().cloned())
+            .collect();
+        let mut tags = config.tags().clone();
+        tags.0.insert(
+            String::new(),
+            HashMap::from([(String::new(), vec![String::new()])]),
+        );
+
+        let given_account = self.update.account.clone();
+        let given_description = self.update.description_prefix.clone();
+        let given_days = self.update.date_shift.clone();
+        let given_tags = (
+            self.update.l1_tag.clone(),
+            self.update.l2_tag.clone(),
+            self.update.l3_tag.clone(),
+        );
+
+        html! {
+            <table>
+            <tr>
+                <th>{format!("{} selected", ctx.props().ids.len())}</th>
+                <th>{"Account"}</th>
+                <th>{"Description prefix"}</th>
+                <th>{"Shift days"}</th>
+                <th>{"L1 Tag"}</th>
+                <th>{"L2 Tag"}</th>
+                <th>{"L3 Tag"}</th>
+                <th>{""}</th>
+            </tr>
+            <tr>
+                <td>
+                <form id={id.clone()}></form>
+                </td>
+                <td>
+                <fields::AccountPicker id={id.clone()} {account_list} {given_account}
+                on_input={ctx.link().callback(BulkEditMsg::UpdateAccount)}/>
+                <fields::FieldErrorMessage error={self.error.clone()} field="account"/>
+                </td>
+                <td>
+                <fields::DescriptionField id={id.clone()} {given_description}
+                on_input={ctx.link().callback(BulkEditMsg::UpdateDescriptionPrefix)}/>
+                <fields::FieldErrorMessage error={self.error.clone()} field="description_prefix"/>
+                </td>
+                <td>
+                <fields::DaysField id={id.clone()} {given_days}
+                on_input={ctx.link().callback(BulkEditMsg::UpdateDateShift)}/>
+                <fields::FieldErrorMessage error={self.error.clone()} field="date_shift"/>
+                </td>
+                <fields::TagPicker id={id.clone()} {tags} {given_tags}
+                on_input={ctx.link().callback(BulkEditMsg::UpdateTags)}/>
+                <td>
+                <button onclick={ctx.link().callback(|_| BulkEditMsg::Submit)}>{"Apply"}</button>
+                <button onclick={ctx.link().callback(|_| BulkEditMsg::Clear)}>{"Clear"}</button>
+                <fields::FieldErrorMessage error={self.error.clone()} field="tags"/>
+                </td>
+            </tr>
+            </table>
+        }
+    }
+}
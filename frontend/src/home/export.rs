@@ -0,0 +1,97 @@
+use chrono::NaiveDate;
+use common::{ExportFormat, ExportOptions, TransactionFilter};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::monthly::DatePicker;
+
+pub enum ExportMsg {
+    Format(AttrValue),
+    Account(AttrValue),
+    From(AttrValue),
+    To(AttrValue),
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ExportProps {
+    pub account_list: Vec<String>,
+}
+
+pub struct ExportComponent {
+    options: ExportOptions,
+}
+
+impl Component for ExportComponent {
+    type Message = ExportMsg;
+    type Properties = ExportProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            options: ExportOptions::default(),
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let filter = &mut self.options.filter;
+        match msg {
+            ExportMsg::Format(format) => {
+                self.options.format = ExportFormat::ALL
+                    .into_iter()
+                    .find(|f| format!("{f:?}") == format.as_str())
+                    .unwrap_or_default();
+            }
+            ExportMsg::Account(account) => {
+                filter.account = (!account.is_empty()).then(|| account.to_string());
+            }
+            ExportMsg::From(d) => filter.from = parse_date(&d),
+            ExportMsg::To(d) => filter.to = parse_date(&d),
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let TransactionFilter {
+            account, from, to, ..
+        } = &self.options.filter;
+        let formats: Html = ExportFormat::ALL
+            .iter()
+            .map(|f| html! { <option selected={*f == self.options.format}>{format!("{f:?}")}</option> })
+            .collect();
+        let accounts: Html = ctx
+            .props()
+            .account_list
+            .iter()
+            .map(|a| html! { <option selected={Some(a) == account.as_ref()}>{a.clone()}</option> })
+            .collect();
+        let href = format!("/api/export?{}", self.options.url_encode());
+
+        html! {
+            <div class="wrapper">
+            <select id="export_format"
+            oninput={ctx.link().callback(|e: InputEvent| {
+                let input = e.target_unchecked_into::<HtmlInputElement>();
+                ExportMsg::Format(AttrValue::from(input.value()))
+            })}>
+                {formats}
+            </select>
+            <select id="export_account"
+            oninput={ctx.link().callback(|e: InputEvent| {
+                let input = e.target_unchecked_into::<HtmlInputElement>();
+                ExportMsg::Account(AttrValue::from(input.value()))
+            })}>
+                <option value="" selected={account.is_none()}>{"All accounts"}</option>
+                {accounts}
+            </select>
+            <DatePicker id="export_from" given_date={from.map(|d| d.to_string()).unwrap_or_default()}
+            on_input={ctx.link().callback(ExportMsg::From)}/>
+            <DatePicker id="export_to" given_date={to.map(|d| d.to_string()).unwrap_or_default()}
+            on_input={ctx.link().callback(ExportMsg::To)}/>
+            <a href={href} download="">{"Download transactions"}</a>
+            </div>
+        }
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
@@ -1,4 +1,4 @@
-use common::Tags;
+use common::{ApiError, Tags};
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
@@ -192,6 +192,49 @@ impl Component for AmountField {
     }
 }
 
+#[derive(PartialEq, Properties)]
+pub struct DaysProps {
+    pub id: AttrValue,
+    pub given_days: AttrValue,
+    pub on_input: Callback<AttrValue>,
+}
+
+/* Whole days, negative for earlier */
+pub struct DaysField;
+
+impl Component for DaysField {
+    type Message = FieldMsg;
+    type Properties = DaysProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            FieldMsg::Update(d) => ctx.props().on_input.emit(d),
+        };
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <input
+                class="form-control"
+                id="days"
+                type="number"
+                step="1"
+                form={ctx.props().id.clone()}
+                value={ctx.props().given_days.clone()}
+                oninput={ ctx.link().callback(|e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    FieldMsg::Update(AttrValue::from(input.value()))
+                }) }
+            />
+        }
+    }
+}
+
 #[derive(PartialEq, Properties)]
 pub struct TagPickerProps {
     pub id: AttrValue,
@@ -361,3 +404,17 @@ impl Component for TagPicker {
         }
     }
 }
+
+#[derive(PartialEq, Properties)]
+pub struct FieldErrorProps {
+    pub error: Option<ApiError>,
+    pub field: AttrValue,
+}
+
+#[function_component(FieldErrorMessage)]
+pub fn field_error_message(FieldErrorProps { error, field }: &FieldErrorProps) -> Html {
+    match error.as_ref().and_then(|e| e.field_error(field)) {
+        Some(message) => html! { <div class="field_error">{message.to_owned()}</div> },
+        None => html! {<></>},
+    }
+}
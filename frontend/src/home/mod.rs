@@ -1,21 +1,26 @@
 mod accounts;
-mod fields;
+mod bulk;
+mod export;
+pub mod fields;
 mod transaction_form;
 mod transactions;
 
 use std::sync::Arc;
 
-use anyhow::anyhow;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use common::{AccountSummary, Config, ConfigOptions, Transaction};
+use common::{
+    AccountSummary, ApiError, Config, ConfigOptions, FieldError, Kpi, KpiSummary, Transaction,
+};
 use yew::prelude::*;
 
 use crate::{
     api,
+    components::ErrorBanner,
     home::{
-        accounts::AccountsSummaryComponent, transaction_form::CreateForm,
+        accounts::AccountsSummaryComponent, export::ExportComponent, transaction_form::CreateForm,
         transactions::TransactionsComponent,
     },
+    insights::AnomaliesComponent,
 };
 
 pub struct AccountData {
@@ -29,12 +34,14 @@ pub struct TransactionsData {
 }
 
 pub enum HomeMsg {
-    Error,
+    Error(ApiError),
     RefreshData,
     NeedUpdateConfig,
     UpdateConfig(Config),
     NeedUpdateAccount,
     UpdateAccount(Vec<AccountSummary>),
+    NeedUpdateKpis,
+    UpdateKpis(KpiSummary),
     NeedUpdateTransactions,
     UpdateTransactions(Vec<Transaction>),
     Back,
@@ -45,6 +52,8 @@ pub struct HomeComponent {
     account_data: AccountData,
     transactions_data: TransactionsData,
     config: Option<Arc<Config>>,
+    kpis: Option<KpiSummary>,
+    error: Option<ApiError>,
 }
 
 impl Component for HomeComponent {
@@ -62,6 +71,8 @@ impl Component for HomeComponent {
                 page: (0, 50),
             },
             config: None,
+            kpis: None,
+            error: None,
         };
 
         ctx.link().send_message(Self::Message::NeedUpdateConfig);
@@ -73,23 +84,46 @@ impl Component for HomeComponent {
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         let mut should_render = false;
         match msg {
-            HomeMsg::Error => (),
+            HomeMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+                should_render = true;
+            }
             HomeMsg::RefreshData => {
                 log::info!("Refreshing data.");
+                self.error = None;
                 ctx.link().send_message(HomeMsg::NeedUpdateAccount);
                 ctx.link().send_message(HomeMsg::NeedUpdateTransactions);
+                ctx.link().send_message(HomeMsg::NeedUpdateKpis);
             }
             HomeMsg::NeedUpdateAccount => {
                 log::info!("Getting all accounts.");
-                ctx.link()
-                    .send_future(async move { HomeMsg::UpdateAccount(api::get_accounts().await) });
+                ctx.link().send_future(async move {
+                    match api::get_accounts().await {
+                        Ok(accounts) => HomeMsg::UpdateAccount(accounts),
+                        Err(e) => HomeMsg::Error(e),
+                    }
+                });
+            }
+            HomeMsg::NeedUpdateKpis => {
+                ctx.link().send_future(async move {
+                    match api::get_kpis().await {
+                        Ok(kpis) => HomeMsg::UpdateKpis(kpis),
+                        Err(e) => HomeMsg::Error(e),
+                    }
+                });
+            }
+            HomeMsg::UpdateKpis(kpis) => {
+                self.kpis = Some(kpis);
+                should_render = true;
             }
             HomeMsg::NeedUpdateConfig => {
                 ctx.link().send_future(async move {
                     let config = api::get_config("all").await;
                     match config {
-                        ConfigOptions::All(c) => HomeMsg::UpdateConfig(c),
-                        _ => HomeMsg::Error,
+                        Ok(ConfigOptions::All(c)) => HomeMsg::UpdateConfig(c),
+                        Ok(_) => HomeMsg::Error(ApiError::internal("wrong config option variant")),
+                        Err(e) => HomeMsg::Error(e),
                     }
                 });
             }
@@ -101,7 +135,10 @@ impl Component for HomeComponent {
                 log::info!("Updating transactions {:?}", self.transactions_data.page);
                 let (offset, limit) = self.transactions_data.page;
                 ctx.link().send_future(async move {
-                    HomeMsg::UpdateTransactions(api::get_transactions(offset, limit).await)
+                    match api::get_transactions(offset, limit).await {
+                        Ok(transactions) => HomeMsg::UpdateTransactions(transactions),
+                        Err(e) => HomeMsg::Error(e),
+                    }
                 });
             }
             HomeMsg::UpdateAccount(accounts) => {
@@ -151,27 +188,44 @@ impl Component for HomeComponent {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let error = html! { <ErrorBanner error={self.error.clone()} /> };
+
         let total = match &self.account_data.total {
             Some(t) => format!("{t:.2}"),
-            None => return "".into(),
+            None => return error,
         };
 
         let accounts = match &self.account_data.accounts {
             Some(a) => a,
-            None => return "".into(),
+            None => return error,
         };
 
         let config = match &self.config {
             Some(c) => c,
-            None => return "".into(),
+            None => return error,
         };
         let transactions = match &self.transactions_data.transactions {
             Some(t) => t,
-            None => return "".into(),
+            None => return error,
+        };
+
+        let kpi_cards = match &self.kpis {
+            Some(kpis) => {
+                let latest = kpis.months.last().cloned().unwrap_or_default();
+                html! {
+                    <>
+                    <KpiCard title={latest.period.clone()} kpi={latest} />
+                    <KpiCard title="Last 12 months" kpi={kpis.trailing_year.clone()} />
+                    </>
+                }
+            }
+            None => html! {<></>},
         };
 
         html! {
             <div>
+            {error}
+            <AnomaliesComponent />
             <div class="row">
             <div class="column left">
                 <h2>{"Accounts"}</h2>
@@ -180,6 +234,7 @@ impl Component for HomeComponent {
                 <div class="wrapper">
                     <div class="info"><h2>{"Total: £"}{total}</h2></div>
                     <div class="info"><h2>{"Budget: £"}{config.budget()}</h2></div>
+                    {kpi_cards}
                 </div>
             </div>
         </div>
@@ -197,6 +252,7 @@ impl Component for HomeComponent {
             </tr>
             <AccountsSummaryComponent accounts={accounts.clone()} />
             </table>
+            <ExportComponent account_list={config.account_list().to_vec()} />
             </div>
             <div class="column right">
             <TransactionsComponent transactions={transactions.clone()}
@@ -211,6 +267,29 @@ impl Component for HomeComponent {
     }
 }
 
+#[derive(Properties, PartialEq)]
+pub struct KpiCardProps {
+    pub title: AttrValue,
+    pub kpi: Kpi,
+}
+
+#[function_component(KpiCard)]
+pub fn kpi_card(KpiCardProps { title, kpi }: &KpiCardProps) -> Html {
+    let savings_rate = kpi
+        .savings_rate
+        .map_or_else(|| "-".to_string(), |r| format!("{:.1}%", r * 100.0));
+
+    html! {
+        <div class="info">
+            <h2>{title.clone()}</h2>
+            <p>{format!("Income: £{:.2}", kpi.income)}</p>
+            <p>{format!("Expense: £{:.2}", kpi.expense)}</p>
+            <p>{format!("Saved: £{:.2} ({savings_rate})", kpi.savings)}</p>
+            <p>{format!("Daily spend: £{:.2}", kpi.average_daily_spend)}</p>
+        </div>
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct UserTransaction {
     pub id: i64,
@@ -221,62 +300,60 @@ pub struct UserTransaction {
     pub l1_tag: AttrValue,
     pub l2_tag: AttrValue,
     pub l3_tag: AttrValue,
+    pub version: i64,
 }
 
 impl UserTransaction {
-    pub fn to_transaction(&self, config: &Config) -> anyhow::Result<Transaction> {
-        let id = self.id;
-        let account = if config
-            .account_list()
-            .iter()
-            .any(|a| a == self.account.as_str())
-        {
-            self.account.to_owned()
-        } else {
-            return Err(anyhow!("Bad account {:?}.", &self.account));
-        };
+    pub fn to_transaction(&self, config: &Config) -> Result<Transaction, ApiError> {
+        let mut field_errors = vec![];
 
         let date = match NaiveDate::parse_from_str(&self.date, "%Y-%m-%d") {
             Ok(d) => NaiveDateTime::new(d, NaiveTime::default()),
-            Err(_) => return Err(anyhow!("Bad date {:?}.", &self.date)),
+            Err(_) => {
+                field_errors.push(FieldError::new(
+                    "date",
+                    format!("Bad date {:?}.", &self.date),
+                ));
+                NaiveDateTime::default()
+            }
         };
 
-        let description = self.description.to_owned();
+        let amount = match self.amount.parse::<f64>() {
+            Ok(a) => a,
+            Err(_) => {
+                field_errors.push(FieldError::new(
+                    "amount",
+                    format!("Bad amount {:?}.", &self.amount),
+                ));
+                0.0
+            }
+        };
 
-        let amount = match &self.amount.parse::<f64>() {
-            Ok(a) => a.to_owned(),
-            Err(_) => return Err(anyhow!("Bad amount {:?}", &self.amount)),
+        let transaction = Transaction {
+            id: self.id,
+            account: self.account.to_string(),
+            date,
+            description: self.description.to_string(),
+            amount,
+            l1_tag: self.l1_tag.to_string(),
+            l2_tag: self.l2_tag.to_string(),
+            l3_tag: self.l3_tag.to_string(),
+            version: self.version,
         };
 
-        let l1_tag: AttrValue;
-        let l2_tag: AttrValue;
-        let l3_tag: AttrValue;
-        if config
-            .tags()
-            .verify_tags(&self.l1_tag, &self.l2_tag, &self.l3_tag)
-        {
-            l1_tag = self.l1_tag.clone();
-            l2_tag = self.l2_tag.clone();
-            l3_tag = self.l3_tag.clone();
-        } else {
-            return Err(anyhow!(
-                "Bad tags: {:?}, {:?}, {:?}.",
-                &self.l1_tag,
-                &self.l2_tag,
-                &self.l3_tag
-            ));
+        if let Err(e) = config.validate_transaction(&transaction) {
+            let parse_failed: Vec<String> = field_errors.iter().map(|f| f.field.clone()).collect();
+            field_errors.extend(
+                e.field_errors
+                    .into_iter()
+                    .filter(|f| !parse_failed.contains(&f.field)),
+            );
         }
 
-        Ok(Transaction {
-            id,
-            account: account.to_string(),
-            date,
-            description: description.to_string(),
-            amount,
-            l1_tag: l1_tag.to_string(),
-            l2_tag: l2_tag.to_string(),
-            l3_tag: l3_tag.to_string(),
-        })
+        if !field_errors.is_empty() {
+            return Err(ApiError::validation(field_errors));
+        }
+        Ok(transaction)
     }
 
     pub fn from_transaction(transaction: &Transaction) -> Self {
@@ -288,6 +365,7 @@ impl UserTransaction {
         let l1_tag = AttrValue::from(transaction.l1_tag.to_owned());
         let l2_tag = AttrValue::from(transaction.l2_tag.to_owned());
         let l3_tag = AttrValue::from(transaction.l3_tag.to_owned());
+        let version = transaction.version;
 
         Self {
             id,
@@ -298,6 +376,7 @@ impl UserTransaction {
             l1_tag,
             l2_tag,
             l3_tag,
+            version,
         }
     }
 }
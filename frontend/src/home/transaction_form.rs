@@ -1,13 +1,13 @@
 use std::sync::Arc;
 
-use common::Config;
+use common::{ApiError, Config};
 use yew::prelude::*;
 
 use super::{fields, UserTransaction};
-use crate::api;
+use crate::{api, components::ErrorBanner};
 
 pub enum CreateFormMsg {
-    Error,
+    Error(ApiError),
     Submit,
     Success(UserTransaction),
     UpdateAccount(AttrValue),
@@ -25,6 +25,7 @@ pub struct CreateFormProps {
 
 pub struct CreateForm {
     transaction: UserTransaction,
+    error: Option<ApiError>,
 }
 
 impl Component for CreateForm {
@@ -35,14 +36,19 @@ impl Component for CreateForm {
         log::info!("Creating form");
         Self {
             transaction: UserTransaction::default(),
+            error: None,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            CreateFormMsg::Error => (),
+            CreateFormMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
             CreateFormMsg::Success(t) => {
                 log::info!("Submitted new transaction");
+                self.error = None;
                 ctx.props().on_submit.emit(());
                 self.transaction = t;
             }
@@ -52,15 +58,17 @@ impl Component for CreateForm {
                     Ok(t) => t,
                     Err(e) => {
                         log::info!("Failed conversion: {e}");
-                        ctx.link().send_message(CreateFormMsg::Error);
+                        ctx.link().send_message(CreateFormMsg::Error(e));
                         return false;
                     }
                 };
                 log::info!("Making API post with {:?}.", transaction);
                 let submitted_transaction = self.transaction.clone();
                 ctx.link().send_future(async move {
-                    api::create_transaction(transaction).await;
-                    CreateFormMsg::Success(submitted_transaction)
+                    match api::create_transaction(transaction).await {
+                        Ok(_) => CreateFormMsg::Success(submitted_transaction),
+                        Err(e) => CreateFormMsg::Error(e),
+                    }
                 });
                 self.transaction = UserTransaction::default();
             }
@@ -102,6 +110,7 @@ impl Component for CreateForm {
 
         yew::html! {
             <>
+                <ErrorBanner error={self.error.clone()} />
                 <table>
                 <tr>
                     <th></th>
@@ -121,22 +130,32 @@ impl Component for CreateForm {
                     <fields::AccountPicker id={id.clone()} account_list={ctx.props().config.account_list().to_owned()}
                     {given_account}
                     on_input={ctx.link().callback(CreateFormMsg::UpdateAccount)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="account"/>
                     </td>
                     <td>
                     <fields::DatePicker id={id.clone()} {given_date}
                     on_input={ctx.link().callback(CreateFormMsg::UpdateDate)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="date"/>
                     </td>
                     <td>
                     <fields::DescriptionField id={id.clone()} {given_description}
                     on_input={ctx.link().callback(CreateFormMsg::UpdateDescription)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="description"/>
                     </td>
                     <td>
                     <fields::AmountField id={id.clone()} {given_amount}
                     on_input={ctx.link().callback(CreateFormMsg::UpdateAmount)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="amount"/>
                     </td>
                     <fields::TagPicker id={id.clone()} tags={ctx.props().config.tags().clone()} {given_tags}
                     on_input={ctx.link().callback(CreateFormMsg::UpdateTags)}/>
                 </tr>
+                <tr>
+                    <td colspan="5"></td>
+                    <td colspan="3">
+                    <fields::FieldErrorMessage error={self.error.clone()} field="tags"/>
+                    </td>
+                </tr>
 
                 </table>
                 <button
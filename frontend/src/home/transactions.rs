@@ -1,17 +1,18 @@
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
 
-use common::{Config, Transaction};
+use common::{ApiError, Config, ErrorCode, Transaction, TransactionPatch};
 use yew::prelude::*;
 
-use super::{fields, UserTransaction};
-use crate::api;
+use super::{bulk::BulkEditBar, fields, UserTransaction};
+use crate::{api, components::ErrorBanner};
 
 pub enum UpdateFormMsg {
-    Error,
+    Error(ApiError),
     Submit,
     Delete,
     Deleted,
     Success(UserTransaction),
+    Conflict(ApiError),
     UpdateAccount(AttrValue),
     UpdateDate(AttrValue),
     UpdateDescription(AttrValue),
@@ -21,13 +22,17 @@ pub enum UpdateFormMsg {
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct UpdateFormProps {
-    pub given_transaction: UserTransaction,
+    pub given_transaction: Transaction,
     pub on_submit: Callback<()>,
+    pub on_select: Callback<i64>,
+    pub on_error: Callback<ApiError>,
+    pub selected: bool,
     pub config: Arc<Config>,
 }
 
 pub struct TransactionComponent {
     transaction: UserTransaction,
+    error: Option<ApiError>,
 }
 
 impl Component for TransactionComponent {
@@ -36,14 +41,20 @@ impl Component for TransactionComponent {
 
     fn create(ctx: &Context<Self>) -> Self {
         Self {
-            transaction: ctx.props().given_transaction.clone(),
+            transaction: UserTransaction::from_transaction(&ctx.props().given_transaction),
+            error: None,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            UpdateFormMsg::Error => (),
+            UpdateFormMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e.clone());
+                ctx.props().on_error.emit(e);
+            }
             UpdateFormMsg::Success(t) => {
+                self.error = None;
                 ctx.props().on_submit.emit(());
                 self.transaction = t;
             }
@@ -53,17 +64,34 @@ impl Component for TransactionComponent {
                     Ok(t) => t,
                     Err(e) => {
                         log::info!("Failed conversion: {e}");
-                        ctx.link().send_message(UpdateFormMsg::Error);
+                        ctx.link().send_message(UpdateFormMsg::Error(e));
                         return false;
                     }
                 };
-                log::info!("Making API patch with {:?}.", transaction);
+                let patch = TransactionPatch::diff(&ctx.props().given_transaction, &transaction);
+                if patch.is_empty() {
+                    log::info!("Nothing to update.");
+                    return false;
+                }
+                log::info!("Making API patch with {:?}.", patch);
                 let submitted_transaction = self.transaction.clone();
                 ctx.link().send_future(async move {
-                    api::update_transaction(transaction).await;
-                    UpdateFormMsg::Success(submitted_transaction)
+                    match api::update_transaction(patch).await {
+                        Ok(version) => UpdateFormMsg::Success(UserTransaction {
+                            version,
+                            ..submitted_transaction
+                        }),
+                        Err(e) if e.code == ErrorCode::Conflict => UpdateFormMsg::Conflict(e),
+                        Err(e) => UpdateFormMsg::Error(e),
+                    }
                 });
-                self.transaction = UserTransaction::default();
+            }
+            UpdateFormMsg::Conflict(e) => {
+                log::warn!("Transaction was changed elsewhere, reloading.");
+                self.transaction =
+                    UserTransaction::from_transaction(&ctx.props().given_transaction);
+                ctx.props().on_error.emit(e);
+                ctx.props().on_submit.emit(());
             }
             UpdateFormMsg::Deleted => {
                 ctx.props().on_submit.emit(());
@@ -72,8 +100,10 @@ impl Component for TransactionComponent {
                 log::info!("Making API delete with {:?}.", self.transaction.id);
                 let id = self.transaction.id;
                 ctx.link().send_future(async move {
-                    api::delete_transaction(id).await;
-                    UpdateFormMsg::Deleted
+                    match api::delete_transaction(id).await {
+                        Ok(()) => UpdateFormMsg::Deleted,
+                        Err(e) => UpdateFormMsg::Error(e),
+                    }
                 });
             }
             UpdateFormMsg::UpdateAccount(account) => {
@@ -101,8 +131,8 @@ impl Component for TransactionComponent {
     }
 
     fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
-        if ctx.props().given_transaction.id != old_props.given_transaction.id {
-            self.transaction = ctx.props().given_transaction.clone();
+        if ctx.props().given_transaction != old_props.given_transaction {
+            self.transaction = UserTransaction::from_transaction(&ctx.props().given_transaction);
         }
         true
     }
@@ -119,33 +149,42 @@ impl Component for TransactionComponent {
             self.transaction.l3_tag.clone(),
         );
 
+        let transaction_id = self.transaction.id;
+
         yew::html! {
                 <tr>
                     <td>
                     <form id={id.clone()}></form>
+                    <input type="checkbox" checked={ctx.props().selected}
+                    onclick={ctx.props().on_select.reform(move |_| transaction_id)}/>
                     </td>
                     <td>
                     <fields::AccountPicker id={id.clone()} account_list={ctx.props().config.account_list().to_owned()}
                     {given_account}
                     on_input={ctx.link().callback(UpdateFormMsg::UpdateAccount)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="account"/>
                     </td>
                     <td>
                     <fields::DatePicker id={id.clone()} {given_date}
                     on_input={ctx.link().callback(UpdateFormMsg::UpdateDate)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="date"/>
                     </td>
                     <td>
                     <fields::DescriptionField id={id.clone()} {given_description}
                     on_input={ctx.link().callback(UpdateFormMsg::UpdateDescription)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="description"/>
                     </td>
                     <td>
                     <fields::AmountField id={id.clone()} {given_amount}
                     on_input={ctx.link().callback(UpdateFormMsg::UpdateAmount)}/>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="amount"/>
                     </td>
                     <fields::TagPicker id={id.clone()} tags={ctx.props().config.tags().clone()} {given_tags}
                     on_input={ctx.link().callback(UpdateFormMsg::UpdateTags)}/>
                     <td>
                     <button onclick={ctx.link().callback(|_| UpdateFormMsg::Submit)}>{"💾"}</button>
                     <button onclick={ctx.link().callback(|_| UpdateFormMsg::Delete)}>{"❌"}</button>
+                    <fields::FieldErrorMessage error={self.error.clone()} field="tags"/>
                     </td>
                 </tr>
 
@@ -162,38 +201,90 @@ pub struct TransactionsComponentProps {
 
 pub enum TransactionsMsg {
     RefreshData,
+    Error(ApiError),
+    ToggleSelected(i64),
+    ClearSelected,
+    BulkUpdated,
 }
 
-pub struct TransactionsComponent {}
+pub struct TransactionsComponent {
+    selected: BTreeSet<i64>,
+    error: Option<ApiError>,
+}
 
 impl Component for TransactionsComponent {
     type Message = TransactionsMsg;
     type Properties = TransactionsComponentProps;
 
     fn create(_ctx: &Context<Self>) -> Self {
-        Self {}
+        Self {
+            selected: BTreeSet::new(),
+            error: None,
+        }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             TransactionsMsg::RefreshData => ctx.props().on_submit.emit(()),
+            TransactionsMsg::Error(e) => self.error = Some(e),
+            TransactionsMsg::ToggleSelected(id) => {
+                if !self.selected.remove(&id) {
+                    self.selected.insert(id);
+                }
+            }
+            TransactionsMsg::ClearSelected => self.selected.clear(),
+            TransactionsMsg::BulkUpdated => {
+                self.selected.clear();
+                self.error = None;
+                ctx.props().on_submit.emit(());
+            }
         }
         true
     }
 
+    /* Forget selections that are no longer listed, or a bulk edit would
+     * change transactions the user can't see */
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        let transactions = &ctx.props().transactions;
+        self.selected
+            .retain(|id| transactions.iter().any(|t| t.id == *id));
+        true
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let transaction_html: Html = ctx.props().transactions.iter()
-        .map(|transaction| {
+        let transaction_html: Html = ctx
+            .props()
+            .transactions
+            .iter()
+            .map(|transaction| {
+                html! {
+                <TransactionComponent given_transaction={transaction.clone()}
+                on_submit={ctx.link().callback(|_| TransactionsMsg::RefreshData)}
+                on_select={ctx.link().callback(TransactionsMsg::ToggleSelected)}
+                on_error={ctx.link().callback(TransactionsMsg::Error)}
+                selected={self.selected.contains(&transaction.id)}
+                config={ctx.props().config.clone()}
+                />
+                }
+            })
+            .collect();
+
+        let bulk_html = if self.selected.is_empty() {
+            html! {<></>}
+        } else {
             html! {
-            <TransactionComponent given_transaction={UserTransaction::from_transaction(transaction)}
-            on_submit={ctx.link().callback(|_| TransactionsMsg::RefreshData)}
-            config={ctx.props().config.clone()}
-            />
+            <BulkEditBar ids={self.selected.iter().copied().collect::<Vec<i64>>()}
+            on_submit={ctx.link().callback(|_| TransactionsMsg::BulkUpdated)}
+            on_clear={ctx.link().callback(|_| TransactionsMsg::ClearSelected)}
+            on_error={ctx.link().callback(TransactionsMsg::Error)}
+            config={ctx.props().config.clone()}/>
             }
-        })
-        .collect();
+        };
 
         html! {
+            <>
+            <ErrorBanner error={self.error.clone()} />
+            {bulk_html}
             <table>
             <tr>
                 <th>{""}</th>
@@ -208,6 +299,7 @@ impl Component for TransactionsComponent {
             </tr>
             {transaction_html}
             </table>
+            </>
         }
     }
 }
@@ -0,0 +1,90 @@
+use common::{Anomaly, AnomalyKind, ApiError};
+use yew::prelude::*;
+
+use crate::{api, components::ErrorBanner};
+
+pub enum AnomaliesMsg {
+    Error(ApiError),
+    NeedAnomalies,
+    UpdateAnomalies(Vec<Anomaly>),
+    Dismiss(usize),
+}
+
+pub struct AnomaliesComponent {
+    anomalies: Vec<Anomaly>,
+    error: Option<ApiError>,
+}
+
+impl Component for AnomaliesComponent {
+    type Message = AnomaliesMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedAnomalies);
+
+        Self {
+            anomalies: vec![],
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            AnomaliesMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            AnomaliesMsg::NeedAnomalies => {
+                ctx.link().send_future(async move {
+                    match api::get_anomalies().await {
+                        Ok(anomalies) => AnomaliesMsg::UpdateAnomalies(anomalies),
+                        Err(e) => AnomaliesMsg::Error(e),
+                    }
+                });
+                return false;
+            }
+            AnomaliesMsg::UpdateAnomalies(anomalies) => {
+                self.error = None;
+                self.anomalies = anomalies;
+            }
+            AnomaliesMsg::Dismiss(index) => {
+                if index < self.anomalies.len() {
+                    self.anomalies.remove(index);
+                }
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.anomalies.is_empty() {
+            return html! { <ErrorBanner error={self.error.clone()} /> };
+        }
+
+        let anomalies_html: Html = self
+            .anomalies
+            .iter()
+            .enumerate()
+            .map(|(index, anomaly)| {
+                let icon = match anomaly.kind {
+                    AnomalyKind::CategorySpike => "📈",
+                    AnomalyKind::LargeTransaction => "💸",
+                    AnomalyKind::PossibleDuplicate => "👯",
+                };
+                html! {
+                    <li>
+                    {icon}{" "}{anomaly.message.clone()}{" "}
+                    <button onclick={ctx.link().callback(move |_| AnomaliesMsg::Dismiss(index))}>{"✖"}</button>
+                    </li>
+                }
+            })
+            .collect();
+
+        html! {
+            <div class="notifications">
+            <ErrorBanner error={self.error.clone()} />
+            <ul>{anomalies_html}</ul>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use common::{ApiError, HoldingValue};
+use web_sys::HtmlTextAreaElement;
+use yew::prelude::*;
+
+use crate::{api, components::ErrorBanner};
+
+pub enum InvestmentsMsg {
+    Error(ApiError),
+    NeedHoldings,
+    UpdateHoldings(Vec<HoldingValue>),
+    UpdatePrices(AttrValue),
+    ImportPrices,
+    Imported(usize),
+}
+
+pub struct InvestmentsComponent {
+    holdings: Vec<HoldingValue>,
+    prices: AttrValue,
+    imported: Option<usize>,
+    error: Option<ApiError>,
+}
+
+impl Component for InvestmentsComponent {
+    type Message = InvestmentsMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedHoldings);
+
+        Self {
+            holdings: vec![],
+            prices: AttrValue::default(),
+            imported: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            InvestmentsMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            InvestmentsMsg::NeedHoldings => ctx.link().send_future(async move {
+                match api::get_holdings().await {
+                    Ok(holdings) => InvestmentsMsg::UpdateHoldings(holdings),
+                    Err(e) => InvestmentsMsg::Error(e),
+                }
+            }),
+            InvestmentsMsg::UpdateHoldings(holdings) => self.holdings = holdings,
+            InvestmentsMsg::UpdatePrices(prices) => self.prices = prices,
+            InvestmentsMsg::ImportPrices => {
+                let prices = self.prices.to_string();
+                ctx.link().send_future(async move {
+                    match api::import_prices(prices).await {
+                        Ok(count) => InvestmentsMsg::Imported(count),
+                        Err(e) => InvestmentsMsg::Error(e),
+                    }
+                });
+            }
+            InvestmentsMsg::Imported(count) => {
+                self.error = None;
+                self.imported = Some(count);
+                self.prices = AttrValue::default();
+                ctx.link().send_message(InvestmentsMsg::NeedHoldings);
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let rows: Html = self
+            .holdings
+            .iter()
+            .map(|h| {
+                let price = match (h.price, h.price_date) {
+                    (Some(price), Some(date)) => format!("£{price:.2} on {date}"),
+                    _ => "No price".to_string(),
+                };
+                html! {
+                    <tr>
+                        <td>{h.holding.account.clone()}</td>
+                        <td>{h.holding.ticker.clone()}</td>
+                        <td>{format!("{:.4}", h.holding.quantity)}</td>
+                        <td>{format!("£{:.2}", h.holding.cost_basis)}</td>
+                        <td>{price}</td>
+                        <td>{format!("£{:.2}", h.market_value)}</td>
+                        <td>{format!("£{:+.2}", h.gain)}</td>
+                    </tr>
+                }
+            })
+            .collect();
+        let total: f64 = self.holdings.iter().map(|h| h.market_value).sum();
+        let imported = self
+            .imported
+            .map_or_else(String::new, |count| format!("Imported {count} prices."));
+
+        html! {
+            <>
+            <ErrorBanner error={self.error.clone()} />
+            <div class="row">
+                <div class="info"><h2>{format!("Market value £{total:.2}")}</h2></div>
+            </div>
+            <table class="data">
+            <tr>
+                <th>{"Account"}</th>
+                <th>{"Ticker"}</th>
+                <th>{"Quantity"}</th>
+                <th>{"Cost basis"}</th>
+                <th>{"Latest price"}</th>
+                <th>{"Market value"}</th>
+                <th>{"Gain"}</th>
+            </tr>
+            {rows}
+            </table>
+            <div class="input_tran">
+            <label for="prices">{"Price history CSV (date,ticker,price)"}</label><br/>
+            <textarea id="prices" rows="8" cols="60" value={self.prices.clone()}
+            oninput={ctx.link().callback(|e: InputEvent| {
+                let input = e.target_unchecked_into::<HtmlTextAreaElement>();
+                InvestmentsMsg::UpdatePrices(AttrValue::from(input.value()))
+            })}/>
+            <br/>
+            <button onclick={ctx.link().callback(|_| InvestmentsMsg::ImportPrices)}>
+            {"Import prices"}
+            </button>
+            <span>{imported}</span>
+            </div>
+            </>
+        }
+    }
+}
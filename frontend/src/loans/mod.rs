@@ -0,0 +1,307 @@
+use chrono::{NaiveDate, Utc};
+use common::{ApiError, ConfigOptions, FieldError, Loan, LoanOptions, LoanStatus};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{
+    api,
+    components::ErrorBanner,
+    home::fields::{AccountPicker, FieldErrorMessage},
+    monthly::DatePicker,
+};
+
+pub enum LoansMsg {
+    Error(ApiError),
+    NeedLoans,
+    UpdateLoans(Vec<LoanStatus>),
+    NeedAccounts,
+    UpdateAccounts(ConfigOptions),
+    UpdateExtra(AttrValue),
+    UpdateField(LoanField, AttrValue),
+    Submit,
+    Delete(i64),
+}
+
+pub enum LoanField {
+    Name,
+    Principal,
+    Rate,
+    Term,
+    StartDate,
+    PaymentDay,
+    Account,
+}
+
+#[derive(Default)]
+struct UserLoan {
+    name: AttrValue,
+    principal: AttrValue,
+    rate: AttrValue,
+    term_months: AttrValue,
+    start_date: AttrValue,
+    payment_day: AttrValue,
+    account: AttrValue,
+}
+
+pub struct LoansComponent {
+    loans: Vec<LoanStatus>,
+    accounts: Vec<String>,
+    extra: AttrValue,
+    loan: UserLoan,
+    error: Option<ApiError>,
+}
+
+impl Component for LoansComponent {
+    type Message = LoansMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedLoans);
+        ctx.link().send_message(Self::Message::NeedAccounts);
+
+        Self {
+            loans: vec![],
+            accounts: vec![],
+            extra: AttrValue::from("0"),
+            loan: UserLoan {
+                start_date: AttrValue::from(Utc::now().date_naive().to_string()),
+                payment_day: AttrValue::from("1"),
+                ..UserLoan::default()
+            },
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            LoansMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            LoansMsg::NeedLoans => {
+                let Ok(extra) = self.extra.parse::<f64>() else {
+                    ctx.link()
+                        .send_message(LoansMsg::Error(ApiError::validation(vec![
+                            FieldError::new("extra", format!("Bad amount {:?}.", &self.extra)),
+                        ])));
+                    return false;
+                };
+                let options = LoanOptions { extra };
+                ctx.link().send_future(async move {
+                    match api::get_loans(&options).await {
+                        Ok(loans) => LoansMsg::UpdateLoans(loans),
+                        Err(e) => LoansMsg::Error(e),
+                    }
+                });
+            }
+            LoansMsg::UpdateLoans(loans) => {
+                self.error = None;
+                self.loans = loans;
+            }
+            LoansMsg::NeedAccounts => ctx.link().send_future(async move {
+                match api::get_config("account_list").await {
+                    Ok(config) => LoansMsg::UpdateAccounts(config),
+                    Err(e) => LoansMsg::Error(e),
+                }
+            }),
+            LoansMsg::UpdateAccounts(config) => match config {
+                ConfigOptions::AccountList(accounts) => {
+                    if self.loan.account.is_empty() {
+                        self.loan.account = accounts.first().cloned().unwrap_or_default().into();
+                    }
+                    self.accounts = accounts;
+                }
+                _ => ctx.link().send_message(LoansMsg::Error(ApiError::internal(
+                    "wrong config option variant",
+                ))),
+            },
+            LoansMsg::UpdateExtra(extra) => {
+                self.extra = extra;
+                ctx.link().send_message(LoansMsg::NeedLoans);
+            }
+            LoansMsg::UpdateField(field, value) => match field {
+                LoanField::Name => self.loan.name = value,
+                LoanField::Principal => self.loan.principal = value,
+                LoanField::Rate => self.loan.rate = value,
+                LoanField::Term => self.loan.term_months = value,
+                LoanField::StartDate => self.loan.start_date = value,
+                LoanField::PaymentDay => self.loan.payment_day = value,
+                LoanField::Account => self.loan.account = value,
+            },
+            LoansMsg::Submit => {
+                let loan = match self.loan.to_loan() {
+                    Ok(loan) => loan,
+                    Err(e) => {
+                        ctx.link().send_message(LoansMsg::Error(e));
+                        return false;
+                    }
+                };
+                ctx.link().send_future(async move {
+                    match api::create_loan(loan).await {
+                        Ok(_) => LoansMsg::NeedLoans,
+                        Err(e) => LoansMsg::Error(e),
+                    }
+                });
+                self.loan.name = AttrValue::default();
+                self.loan.principal = AttrValue::default();
+            }
+            LoansMsg::Delete(id) => ctx.link().send_future(async move {
+                match api::delete_loan(id).await {
+                    Ok(_) => LoansMsg::NeedLoans,
+                    Err(e) => LoansMsg::Error(e),
+                }
+            }),
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let rows: Html = self
+            .loans
+            .iter()
+            .map(|l| {
+                let id = l.loan.id;
+                let paid_off =
+                    |d: Option<NaiveDate>| d.map_or_else(|| "Never".to_string(), |d| d.to_string());
+                html! {
+                    <tr>
+                        <td>{l.loan.name.clone()}</td>
+                        <td>{l.loan.account.clone()}</td>
+                        <td>{format!("£{:.2}", l.loan.monthly_payment())}</td>
+                        <td>{format!("£{:.2}", l.balance)}</td>
+                        <td>{format!("£{:.2}", l.interest_paid)}</td>
+                        <td>{format!("{} of {}", l.payments_matched, l.payments_due)}</td>
+                        <td>{paid_off(l.baseline_payoff_date)}</td>
+                        <td>{paid_off(l.payoff_date)}</td>
+                        <td>{format!("£{:.2}", l.interest_saved())}</td>
+                        <td>
+                        <button onclick={ctx.link().callback(move |_| LoansMsg::Delete(id))}>
+                        {"Delete"}
+                        </button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect();
+
+        let field = |id: &'static str, value: &AttrValue, make: fn() -> LoanField| {
+            html! {
+                <td>
+                <input class="form-control" {id} value={value.clone()}
+                oninput={ctx.link().callback(move |e: InputEvent| {
+                    let input = e.target_unchecked_into::<HtmlInputElement>();
+                    LoansMsg::UpdateField(make(), AttrValue::from(input.value()))
+                })}/>
+                <FieldErrorMessage error={self.error.clone()} field={id}/>
+                </td>
+            }
+        };
+
+        html! {
+            <>
+            <ErrorBanner error={self.error.clone()} />
+            <div class="wrapper">
+            <label for="extra">{"Extra monthly payment £"}</label>
+            <input class="form-control" id="extra" value={self.extra.clone()}
+            onchange={ctx.link().callback(|e: Event| {
+                let input = e.target_unchecked_into::<HtmlInputElement>();
+                LoansMsg::UpdateExtra(AttrValue::from(input.value()))
+            })}/>
+            <FieldErrorMessage error={self.error.clone()} field="extra"/>
+            </div>
+            <table class="data">
+            <tr>
+                <th>{"Loan"}</th>
+                <th>{"Account"}</th>
+                <th>{"Monthly payment"}</th>
+                <th>{"Balance"}</th>
+                <th>{"Interest paid"}</th>
+                <th title="Repayments tagged with the loan name as the L2 tag">{"Payments matched"}</th>
+                <th>{"Payoff"}</th>
+                <th>{"Payoff with extra"}</th>
+                <th>{"Interest saved"}</th>
+                <th></th>
+            </tr>
+            {rows}
+            </table>
+            <table class="input_tran">
+            <tr>
+                <th><label for="name">{"Name"}</label></th>
+                <th><label for="principal">{"Principal"}</label></th>
+                <th><label for="rate">{"Rate %"}</label></th>
+                <th><label for="term_months">{"Term (months)"}</label></th>
+                <th><label for="start_date">{"Start date"}</label></th>
+                <th><label for="payment_day">{"Payment day"}</label></th>
+                <th><label for="account">{"Account"}</label></th>
+            </tr>
+            <tr>
+                {field("name", &self.loan.name, || LoanField::Name)}
+                {field("principal", &self.loan.principal, || LoanField::Principal)}
+                {field("rate", &self.loan.rate, || LoanField::Rate)}
+                {field("term_months", &self.loan.term_months, || LoanField::Term)}
+                <td>
+                <DatePicker id="start_date" given_date={self.loan.start_date.clone()}
+                on_input={ctx.link().callback(|d| LoansMsg::UpdateField(LoanField::StartDate, d))}/>
+                <FieldErrorMessage error={self.error.clone()} field="start_date"/>
+                </td>
+                {field("payment_day", &self.loan.payment_day, || LoanField::PaymentDay)}
+                <td>
+                <AccountPicker id="loan" account_list={self.accounts.clone()}
+                given_account={self.loan.account.clone()}
+                on_input={ctx.link().callback(|a| LoansMsg::UpdateField(LoanField::Account, a))}/>
+                <FieldErrorMessage error={self.error.clone()} field="account"/>
+                </td>
+            </tr>
+            </table>
+            <button onclick={ctx.link().callback(|_| LoansMsg::Submit)}>{"Add loan"}</button>
+            </>
+        }
+    }
+}
+
+impl UserLoan {
+    fn to_loan(&self) -> Result<Loan, ApiError> {
+        let mut field_errors = vec![];
+
+        let mut number = |field: &str, value: &AttrValue| {
+            value.parse::<f64>().unwrap_or_else(|_| {
+                field_errors.push(FieldError::new(field, format!("Bad number {value:?}.")));
+                0.0
+            })
+        };
+        let principal = number("principal", &self.principal);
+        let rate = number("rate", &self.rate);
+
+        let mut whole = |field: &str, value: &AttrValue| {
+            value.parse::<u32>().unwrap_or_else(|_| {
+                field_errors.push(FieldError::new(field, format!("Bad number {value:?}.")));
+                0
+            })
+        };
+        let term_months = whole("term_months", &self.term_months);
+        let payment_day = whole("payment_day", &self.payment_day);
+
+        let start_date =
+            NaiveDate::parse_from_str(&self.start_date, "%Y-%m-%d").unwrap_or_else(|_| {
+                field_errors.push(FieldError::new(
+                    "start_date",
+                    format!("Bad date {:?}.", &self.start_date),
+                ));
+                NaiveDate::default()
+            });
+
+        if !field_errors.is_empty() {
+            return Err(ApiError::validation(field_errors));
+        }
+        Ok(Loan {
+            id: 0,
+            name: self.name.to_string(),
+            principal,
+            rate,
+            term_months,
+            start_date,
+            payment_day,
+            account: self.account.to_string(),
+        })
+    }
+}
@@ -6,14 +6,29 @@ use yew_router::prelude::*;
 mod api;
 mod balance;
 mod budget;
+mod compare;
 mod components;
+mod flows;
+mod goals;
 mod home;
+mod insights;
+mod investments;
+mod loans;
 mod monthly;
+mod tax;
+mod trash;
 
 use balance::BalanceComponent;
 use budget::BudgetComponent;
+use compare::CompareComponent;
+use flows::FlowsComponent;
+use goals::GoalsComponent;
 use home::HomeComponent;
+use investments::InvestmentsComponent;
+use loans::LoansComponent;
 use monthly::MonthlyComponent;
+use tax::TaxComponent;
+use trash::TrashComponent;
 
 #[derive(Routable, PartialEq, Eq, Clone, Debug)]
 pub enum Route {
@@ -23,8 +38,22 @@ pub enum Route {
     Balance,
     #[at("/budget")]
     Budget,
+    #[at("/goals")]
+    Goals,
+    #[at("/loans")]
+    Loans,
+    #[at("/investments")]
+    Investments,
+    #[at("/tax")]
+    Tax,
     #[at("/monthly")]
     Monthly,
+    #[at("/compare")]
+    Compare,
+    #[at("/flows")]
+    Flows,
+    #[at("/trash")]
+    Trash,
 }
 
 pub struct App {}
@@ -44,7 +73,14 @@ impl Component for App {
                     <li><Link<Route> to={Route::Home}>{"Home"}</Link<Route>></li><br/>
                     <li><Link<Route> to={Route::Balance}>{"Balance History"}</Link<Route>></li><br/>
                     <li><Link<Route> to={Route::Budget}>{"Budget Progress"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Goals}>{"Savings Goals"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Loans}>{"Loans"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Investments}>{"Investments"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Tax}>{"Tax Year"}</Link<Route>></li><br/>
                     <li><Link<Route> to={Route::Monthly}>{"Monthly  Summary"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Compare}>{"Compare"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Flows}>{"Income Flows"}</Link<Route>></li><br/>
+                    <li><Link<Route> to={Route::Trash}>{"Trash"}</Link<Route>></li><br/>
                 </div>
                 <main>
                     <Switch<Route> render={switch} />
@@ -65,9 +101,30 @@ fn switch(routes: Route) -> Html {
         Route::Budget => {
             html! { <BudgetComponent /> }
         }
+        Route::Goals => {
+            html! { <GoalsComponent /> }
+        }
+        Route::Loans => {
+            html! { <LoansComponent /> }
+        }
+        Route::Investments => {
+            html! { <InvestmentsComponent /> }
+        }
+        Route::Tax => {
+            html! { <TaxComponent /> }
+        }
         Route::Monthly => {
             html! { <MonthlyComponent /> }
         }
+        Route::Compare => {
+            html! { <CompareComponent /> }
+        }
+        Route::Flows => {
+            html! { <FlowsComponent /> }
+        }
+        Route::Trash => {
+            html! { <TrashComponent /> }
+        }
     }
 }
 
@@ -1,13 +1,17 @@
-use std::sync::Arc;
+use std::{iter, sync::Arc};
 
 use chrono::{NaiveDate, Utc};
 use common::{
-    BudgetProgress, BudgetProgressOptions, CategorySpend, CategorySpendOptions, ConfigOptions,
+    ApiError, BudgetProgress, BudgetProgressOptions, CategorySpend, CategorySpendOptions,
+    ConfigOptions, TagLevel,
 };
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
-use crate::{api, components::CategorySpendComponent};
+use crate::{
+    api,
+    components::{CategorySpendComponent, ErrorBanner},
+};
 
 pub enum FieldMsg {
     Update(AttrValue),
@@ -56,6 +60,7 @@ impl Component for DatePicker {
 }
 
 pub enum MonthlyMsg {
+    Error(ApiError),
     UpdateDate(AttrValue),
     NeedProgressData,
     UpdateProgressData(BudgetProgress),
@@ -63,6 +68,8 @@ pub enum MonthlyMsg {
     UpdateCategorySpend(Vec<CategorySpend>),
     NeedCategories,
     UpdateCategories(ConfigOptions),
+    DrillDown(String),
+    DrillUp(usize),
 }
 
 pub struct MonthlyComponent {
@@ -70,6 +77,8 @@ pub struct MonthlyComponent {
     budget_progress: Option<BudgetProgress>,
     category_spend: Option<Arc<Vec<CategorySpend>>>,
     categories: Option<Arc<Vec<String>>>,
+    tag_path: Vec<String>,
+    error: Option<ApiError>,
 }
 
 impl Component for MonthlyComponent {
@@ -85,30 +94,46 @@ impl Component for MonthlyComponent {
             budget_progress: None,
             category_spend: None,
             categories: None,
+            tag_path: vec![],
+            error: None,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
+            MonthlyMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
             MonthlyMsg::UpdateDate(d) => {
                 self.date = NaiveDate::parse_from_str(&d, "%Y-%m-%d").unwrap();
                 ctx.link().send_message(Self::Message::NeedProgressData);
                 ctx.link().send_message(Self::Message::NeedCategorySpend);
             }
             MonthlyMsg::NeedCategories => ctx.link().send_future(async move {
-                MonthlyMsg::UpdateCategories(api::get_config("period_items").await)
+                match api::get_config("period_items").await {
+                    Ok(config) => MonthlyMsg::UpdateCategories(config),
+                    Err(e) => MonthlyMsg::Error(e),
+                }
             }),
             MonthlyMsg::UpdateCategories(config) => match config {
                 ConfigOptions::PeriodItems(c) => {
                     self.categories = Some(Arc::new(c));
                     ctx.link().send_message(Self::Message::NeedCategorySpend);
                 }
-                _ => panic!("wrong config option variant"),
+                _ => ctx
+                    .link()
+                    .send_message(MonthlyMsg::Error(ApiError::internal(
+                        "wrong config option variant",
+                    ))),
             },
             MonthlyMsg::NeedProgressData => {
                 let options = BudgetProgressOptions { date: self.date };
                 ctx.link().send_future(async move {
-                    MonthlyMsg::UpdateProgressData(api::budget_progress(&options).await)
+                    match api::budget_progress(&options).await {
+                        Ok(progress) => MonthlyMsg::UpdateProgressData(progress),
+                        Err(e) => MonthlyMsg::Error(e),
+                    }
                 });
             }
             MonthlyMsg::UpdateProgressData(spend) => {
@@ -122,14 +147,27 @@ impl Component for MonthlyComponent {
                 let options = CategorySpendOptions {
                     date: self.date,
                     l1_tags: categories.clone().to_vec(),
+                    level: TagLevel::from_depth(self.tag_path.len()).unwrap_or(TagLevel::L3),
+                    parent: self.tag_path.clone(),
                 };
                 ctx.link().send_future(async move {
-                    MonthlyMsg::UpdateCategorySpend(api::category_spend(&options).await)
+                    match api::category_spend(&options).await {
+                        Ok(spend) => MonthlyMsg::UpdateCategorySpend(spend),
+                        Err(e) => MonthlyMsg::Error(e),
+                    }
                 });
             }
             MonthlyMsg::UpdateCategorySpend(category) => {
                 self.category_spend = Some(Arc::new(category));
             }
+            MonthlyMsg::DrillDown(tag) => {
+                self.tag_path.push(tag);
+                ctx.link().send_message(MonthlyMsg::NeedCategorySpend);
+            }
+            MonthlyMsg::DrillUp(depth) => {
+                self.tag_path.truncate(depth);
+                ctx.link().send_message(MonthlyMsg::NeedCategorySpend);
+            }
         }
         true
     }
@@ -141,6 +179,7 @@ impl Component for MonthlyComponent {
             <>
             <DatePicker id={id} {given_date}
             on_input={ctx.link().callback(MonthlyMsg::UpdateDate)}/>
+            <ErrorBanner error={self.error.clone()} />
             </>
         };
         let Some(budget_progress) = &self.budget_progress else {
@@ -156,6 +195,25 @@ impl Component for MonthlyComponent {
         let total_spend: f64 = category_spend.iter().filter_map(|c| c.amount).sum();
         let total_spend = format!("£{:.2}", total_spend);
 
+        let breadcrumb: Html = iter::once("All".to_string())
+            .chain(self.tag_path.iter().cloned())
+            .enumerate()
+            .map(|(depth, tag)| {
+                html! {
+                    <>
+                    {if depth > 0 { " › " } else { "" }}
+                    <button disabled={depth == self.tag_path.len()}
+                    onclick={ctx.link().callback(move |_| MonthlyMsg::DrillUp(depth))}>
+                    {tag}
+                    </button>
+                    </>
+                }
+            })
+            .collect();
+        /* l3 is as deep as the tags go */
+        let on_select = (self.tag_path.len() < TagLevel::L3.depth())
+            .then(|| ctx.link().callback(MonthlyMsg::DrillDown));
+
         html! {
         <>
         {date_form}
@@ -165,9 +223,12 @@ impl Component for MonthlyComponent {
                 <div class="info"><h2>{"Total Spend "} {total_spend}</h2></div>
             </div>
         </div>
+        <div class="row">
+            <div class="wrapper">{breadcrumb}</div>
+        </div>
         <div class="row">
         <div id="chart" class="chart">
-            <CategorySpendComponent {category_spend} />
+            <CategorySpendComponent {category_spend} {on_select} />
         </div>
         </div>
         </>
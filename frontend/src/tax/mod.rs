@@ -0,0 +1,133 @@
+use chrono::Utc;
+use common::{ApiError, TaxLine, TaxYear, TaxYearOptions, TaxYearReport};
+use yew::prelude::*;
+
+use crate::{api, components::ErrorBanner};
+
+pub enum TaxMsg {
+    Error(ApiError),
+    NeedReport,
+    UpdateReport(TaxYearReport),
+    PrevYear,
+    NextYear,
+    Print,
+}
+
+pub struct TaxComponent {
+    year: TaxYear,
+    report: Option<TaxYearReport>,
+    error: Option<ApiError>,
+}
+
+impl Component for TaxComponent {
+    type Message = TaxMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Self::Message::NeedReport);
+
+        /* Default to the last complete tax year */
+        Self {
+            year: TaxYear::containing(Utc::now().date_naive()).prev(),
+            report: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TaxMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+            }
+            TaxMsg::NeedReport => {
+                let options = TaxYearOptions { year: self.year.0 };
+                ctx.link().send_future(async move {
+                    match api::tax_year_report(&options).await {
+                        Ok(report) => TaxMsg::UpdateReport(report),
+                        Err(e) => TaxMsg::Error(e),
+                    }
+                });
+            }
+            TaxMsg::UpdateReport(report) => {
+                self.error = None;
+                self.report = Some(report);
+            }
+            TaxMsg::PrevYear => {
+                self.year = self.year.prev();
+                ctx.link().send_message(TaxMsg::NeedReport);
+            }
+            TaxMsg::NextYear => {
+                self.year = self.year.next();
+                ctx.link().send_message(TaxMsg::NeedReport);
+            }
+            TaxMsg::Print => {
+                if let Some(window) = web_sys::window() {
+                    if let Err(e) = window.print() {
+                        log::error!("{e:?}");
+                    }
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let export = format!(
+            "/api/tax/export?{}",
+            TaxYearOptions { year: self.year.0 }.url_encode()
+        );
+        let controls = html! {
+            <>
+            <div class="wrapper no_print">
+            <button onclick={ctx.link().callback(|_| TaxMsg::PrevYear)}>{"Previous year"}</button>
+            <button onclick={ctx.link().callback(|_| TaxMsg::NextYear)}>{"Next year"}</button>
+            <a href={export} download="">{"Download CSV"}</a>
+            <button onclick={ctx.link().callback(|_| TaxMsg::Print)}>{"Print"}</button>
+            </div>
+            <ErrorBanner error={self.error.clone()} />
+            </>
+        };
+        let Some(report) = &self.report else {
+            return controls;
+        };
+
+        html! {
+            <>
+            {controls}
+            <h2>{format!("Tax year {} ({} to {})", report.year, report.year.start(),
+                report.year.end().pred_opt().unwrap_or_else(|| report.year.end()))}</h2>
+            <table class="data">
+            <tr><th colspan="2">{"Income"}</th></tr>
+            {lines(&report.income)}
+            <tr><td>{"Interest"}</td><td>{format!("£{:.2}", report.interest)}</td></tr>
+            <tr><td>{"Dividends"}</td><td>{format!("£{:.2}", report.dividends)}</td></tr>
+            <tr><th>{"Total income"}</th><th>{format!("£{:.2}", report.total_income())}</th></tr>
+            <tr><th colspan="2">{"Pension"}</th></tr>
+            <tr>
+                <td>{"Contributions"}</td>
+                <td>{format!("£{:.2}", report.pension_contributions)}</td>
+            </tr>
+            <tr><th colspan="2">{"Deductible expenses"}</th></tr>
+            {lines(&report.deductible)}
+            <tr>
+                <th>{"Total deductible"}</th>
+                <th>{format!("£{:.2}", report.total_deductible())}</th>
+            </tr>
+            </table>
+            </>
+        }
+    }
+}
+
+fn lines(lines: &[TaxLine]) -> Html {
+    lines
+        .iter()
+        .map(|l| {
+            html! {
+                <tr><td>{l.name.clone()}</td><td>{format!("£{:.2}", l.amount)}</td></tr>
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use common::{ApiError, Transaction};
+use yew::prelude::*;
+
+use crate::{api, components::ErrorBanner};
+
+pub enum TrashMsg {
+    Error(ApiError),
+    NeedUpdateTransactions,
+    UpdateTransactions(Vec<Transaction>),
+    Restore(i64),
+    Purge(i64),
+    Back,
+    Forward,
+}
+
+pub struct TrashComponent {
+    transactions: Option<Arc<Vec<Transaction>>>,
+    page: (usize, usize),
+    error: Option<ApiError>,
+}
+
+impl Component for TrashComponent {
+    type Message = TrashMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link()
+            .send_message(Self::Message::NeedUpdateTransactions);
+
+        Self {
+            transactions: None,
+            page: (0, 50),
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let mut should_render = false;
+        match msg {
+            TrashMsg::Error(e) => {
+                log::error!("{e}");
+                self.error = Some(e);
+                should_render = true;
+            }
+            TrashMsg::NeedUpdateTransactions => {
+                log::info!("Updating deleted transactions {:?}", self.page);
+                let (offset, limit) = self.page;
+                ctx.link().send_future(async move {
+                    match api::get_trash(offset, limit).await {
+                        Ok(transactions) => TrashMsg::UpdateTransactions(transactions),
+                        Err(e) => TrashMsg::Error(e),
+                    }
+                });
+            }
+            TrashMsg::UpdateTransactions(transactions) => {
+                if transactions.is_empty() && self.page.0 > 0 {
+                    /* Gone too far, let's go back */
+                    ctx.link().send_message(TrashMsg::Forward);
+                } else {
+                    self.error = None;
+                    self.transactions = Some(Arc::new(transactions));
+                    should_render = true;
+                }
+            }
+            TrashMsg::Restore(id) => {
+                log::info!("Making API restore with {:?}.", id);
+                ctx.link().send_future(async move {
+                    match api::restore_transaction(id).await {
+                        Ok(()) => TrashMsg::NeedUpdateTransactions,
+                        Err(e) => TrashMsg::Error(e),
+                    }
+                });
+            }
+            TrashMsg::Purge(id) => {
+                log::info!("Making API purge with {:?}.", id);
+                ctx.link().send_future(async move {
+                    match api::purge_transaction(id).await {
+                        Ok(()) => TrashMsg::NeedUpdateTransactions,
+                        Err(e) => TrashMsg::Error(e),
+                    }
+                });
+            }
+            TrashMsg::Back => {
+                let Some(transactions) = &self.transactions else {
+                    return false;
+                };
+                if transactions.len() == self.page.1 {
+                    self.page.0 += self.page.1;
+                    ctx.link().send_message(TrashMsg::NeedUpdateTransactions);
+                }
+            }
+            TrashMsg::Forward => {
+                self.page.0 = self.page.0.saturating_sub(self.page.1);
+                ctx.link().send_message(TrashMsg::NeedUpdateTransactions);
+            }
+        }
+        should_render
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let error = html! { <ErrorBanner error={self.error.clone()} /> };
+        let Some(transactions) = &self.transactions else {
+            return error;
+        };
+
+        let transaction_html: Html = transactions
+            .iter()
+            .map(|t| {
+                let id = t.id;
+                html! {
+                    <tr>
+                        <td>{t.account.clone()}</td>
+                        <td>{t.date.date().to_string()}</td>
+                        <td>{t.description.clone()}</td>
+                        <td>{format!("{:.2}", t.amount)}</td>
+                        <td>{t.l1_tag.clone()}</td>
+                        <td>{t.l2_tag.clone()}</td>
+                        <td>{t.l3_tag.clone()}</td>
+                        <td>
+                        <button onclick={ctx.link().callback(move |_| TrashMsg::Restore(id))}>{"↩"}</button>
+                        <button onclick={ctx.link().callback(move |_| TrashMsg::Purge(id))}>{"🗑"}</button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect();
+
+        html! {
+            <div class="wrapper">
+            <h2>{"Trash"}</h2>
+            {error}
+            <table class="data">
+            <tr>
+                <th>{"Account"}</th>
+                <th>{"Date"}</th>
+                <th>{"Description"}</th>
+                <th>{"Amount"}</th>
+                <th>{"L1 Tag"}</th>
+                <th>{"L2 Tag"}</th>
+                <th>{"L3 Tag"}</th>
+                <th>{""}</th>
+            </tr>
+            {transaction_html}
+            </table>
+            <button onclick={ctx.link().callback(|_| TrashMsg::Back)}>{"back"}</button>
+            <button onclick={ctx.link().callback(|_| TrashMsg::Forward)}>{"forward"}</button>
+            </div>
+        }
+    }
+}